@@ -0,0 +1,143 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use driedee_rs::core::{
+    camera::Camera, files::parse_obj, frame_buffer::FrameBuffer, mesh::Mesh, renderer::Renderer,
+    scene_object::SceneObject, triangle::Triangle, vector_3d::Vector3D,
+};
+use std::hint::black_box;
+
+const MODELS: [&str; 2] = ["teapot.obj", "mountains.obj"];
+const RESOLUTIONS: [(u32, u32); 3] = [(320, 240), (1280, 720), (1920, 1080)];
+
+fn triangle(points: [(f32, f32, f32); 3]) -> Triangle {
+    let mut triangle = Triangle::new();
+    for (vector, (x, y, z)) in triangle.vectors.iter_mut().zip(points) {
+        *vector = Vector3D::from_coords(x, y, z);
+    }
+    triangle
+}
+
+fn obj_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("obj_parsing");
+
+    for model in MODELS {
+        // Read the file once, so only parsing is measured
+        let data = std::fs::read(model).expect("Error reading obj file");
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        group.bench_with_input(BenchmarkId::from_parameter(model), &data, |b, data| {
+            let mut triangles = vec![];
+            let mut polygons = vec![];
+            b.iter(|| parse_obj(black_box(data.as_slice()), &mut triangles, &mut polygons))
+        });
+    }
+
+    group.finish();
+}
+
+fn clipping(c: &mut Criterion) {
+    let plane_position = Vector3D::from_coords(0.0, 0.0, 0.1);
+    let plane_normal = Vector3D::from_coords(0.0, 0.0, 1.0);
+    let cases = [
+        (
+            "inside",
+            [(0.0, 0.0, 1.0), (1.0, 0.0, 1.0), (0.0, 1.0, 1.0)],
+        ),
+        (
+            "one_inside",
+            [(0.0, 0.0, 1.0), (1.0, 0.0, -1.0), (0.0, 1.0, -1.0)],
+        ),
+        (
+            "two_inside",
+            [(0.0, 0.0, 1.0), (1.0, 0.0, 1.0), (0.0, 1.0, -1.0)],
+        ),
+        (
+            "outside",
+            [(0.0, 0.0, -1.0), (1.0, 0.0, -1.0), (0.0, 1.0, -1.0)],
+        ),
+    ];
+    let mut group = c.benchmark_group("clipping");
+
+    for (name, points) in cases {
+        let triangle = triangle(points);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &triangle,
+            |b, triangle| {
+                b.iter(|| black_box(triangle).clip_against_plane(plane_position, plane_normal))
+            },
+        );
+    }
+
+    // A triangle sticking out of the screen on every side
+    let renderer = Renderer::new(1280, 720);
+    let triangle = triangle([
+        (-500.0, -500.0, 0.5),
+        (1800.0, 360.0, 0.5),
+        (-500.0, 1200.0, 0.5),
+    ]);
+    group.bench_function("screen_edges", |b| {
+        b.iter(|| renderer.clip_to_screen(black_box(triangle.clone())))
+    });
+
+    group.finish();
+}
+
+fn rasterization(c: &mut Criterion) {
+    let cases = [
+        (
+            "small",
+            [
+                (100.0, 100.0, 0.5),
+                (110.0, 100.0, 0.5),
+                (100.0, 110.0, 0.5),
+            ],
+        ),
+        (
+            "large",
+            [(0.0, 0.0, 0.5), (1279.0, 0.0, 0.5), (640.0, 719.0, 0.5)],
+        ),
+    ];
+    let mut frame_buffer = FrameBuffer::new(1280, 720);
+    let mut group = c.benchmark_group("rasterization");
+
+    for (name, points) in cases {
+        let triangle = triangle(points);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &triangle,
+            |b, triangle| b.iter(|| frame_buffer.draw_filled_triangle(black_box(triangle))),
+        );
+    }
+
+    group.finish();
+}
+
+// Whole frames rendered into a frame buffer, placed the same way the engine
+// places a single model
+fn frame(c: &mut Criterion) {
+    // At the origin looking down +z, like the engine starts out
+    let camera = Camera::new();
+    let mut group = c.benchmark_group("frame");
+    group.sample_size(20);
+
+    for model in MODELS {
+        let objects = [SceneObject::new(
+            Mesh::from_file(model).unwrap(),
+            Vector3D::from_coords(0.0, 0.0, 4.0),
+            1,
+        )];
+
+        for (size_x, size_y) in RESOLUTIONS {
+            let mut renderer = Renderer::new(size_x, size_y);
+            let id = BenchmarkId::new(model, format!("{}x{}", size_x, size_y));
+            group.bench_function(id, |b| {
+                b.iter(|| renderer.render(black_box(&objects), &camera))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, obj_parsing, clipping, rasterization, frame);
+criterion_main!(benches);
@@ -0,0 +1,102 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use driedee_rs::core::{
+    camera::Camera, frustum::Frustum, matrix_4x4::Matrix4X4, mesh::Mesh, scene_object::SceneObject,
+    vector_3d::Vector3D, vector_4d::Vector4D, vertex_batch::VertexBatch,
+};
+use std::hint::black_box;
+
+fn world_view_projection() -> Matrix4X4 {
+    let rotation = &Matrix4X4::from_rotation_x(0.3) * &Matrix4X4::from_rotation_y(-1.1);
+    let world = &rotation * &Matrix4X4::from_translation(1.0, -2.0, 5.0);
+    &world * &Matrix4X4::from_projection(90.0, 0.5625, 0.1, 1000.0)
+}
+
+fn vertices(count: usize) -> Vec<Vector3D> {
+    (0..count)
+        .map(|i| {
+            let i = i as f32;
+            Vector3D::from_coords(i.sin() * 10.0, i.cos() * 10.0, (i * 0.37).sin() * 10.0)
+        })
+        .collect()
+}
+
+// Compares one vertex at a time against the batched scalar and SIMD paths
+fn transform(c: &mut Criterion) {
+    let matrix = world_view_projection();
+    let mut group = c.benchmark_group("transform");
+
+    for count in [1_000, 20_000, 500_000] {
+        let vectors = vertices(count);
+        let batch = VertexBatch::from_vectors(&vectors);
+        let mut output = VertexBatch::new();
+        let mut single_output: Vec<Vector4D> = Vec::with_capacity(count);
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_with_input(BenchmarkId::new("single", count), &vectors, |b, vectors| {
+            b.iter(|| {
+                single_output.clear();
+                single_output.extend(
+                    vectors
+                        .iter()
+                        .map(|vector| &matrix * &Vector4D::from_point(black_box(vector))),
+                );
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("batch_scalar", count),
+            &batch,
+            |b, batch| b.iter(|| batch.transform_scalar_into(black_box(&matrix), &mut output)),
+        );
+        group.bench_with_input(BenchmarkId::new("batch_simd", count), &batch, |b, batch| {
+            b.iter(|| batch.transform_into(black_box(&matrix), &mut output))
+        });
+    }
+
+    group.finish();
+}
+
+// What the renderer does per object: transforming the vertices to world and view
+// space, either all of them or only those of the triangles the BVH finds in view.
+// The teapot is placed like the engine does and turned half out of view.
+fn culled_transform(c: &mut Criterion) {
+    let object = SceneObject::new(
+        Mesh::from_file("teapot.obj").unwrap(),
+        Vector3D::from_coords(0.0, 0.0, 4.0),
+        1,
+    );
+    let vertex_batch = &object.lod_chain().vertex_batches[0];
+    let world = object.world_matrix();
+    let (mut world_output, mut view_output) = (VertexBatch::new(), VertexBatch::new());
+    let mut group = c.benchmark_group("culled_transform");
+
+    for (name, yaw) in [("in_view", 0.0), ("partly_culled", 0.9)] {
+        let mut camera = Camera::new();
+        camera.rotate(yaw, 0.0);
+        let view = camera.view_matrix();
+        let frustum = Frustum::from_matrices(&view, &camera.projection_matrix(0.5625));
+        let visible = object.lod_chain().bvhs[0].query_frustum(&frustum.to_object_space(&world));
+        let world_view = &world * &view;
+
+        group.bench_function(BenchmarkId::new("all", name), |b| {
+            b.iter(|| {
+                vertex_batch.transform_into(black_box(&world), &mut world_output);
+                vertex_batch.transform_into(black_box(&world_view), &mut view_output);
+            })
+        });
+        group.bench_function(BenchmarkId::new("visible", name), |b| {
+            b.iter(|| {
+                vertex_batch.transform_triangles_into(
+                    &world,
+                    black_box(&visible),
+                    &mut world_output,
+                );
+                vertex_batch.transform_triangles_into(&world_view, &visible, &mut view_output);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, transform, culled_transform);
+criterion_main!(benches);
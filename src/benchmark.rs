@@ -0,0 +1,160 @@
+use std::{
+    f32::consts::TAU,
+    fs::File,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use driedee_rs::core::{engine::Engine, renderer::FrameStats, vector_3d::Vector3D};
+use sdl2::{event::Event, EventPump};
+
+// How long a frame took in total, and how long each stage took
+struct FrameRecord {
+    frame_time: Duration,
+    stats: FrameStats,
+}
+
+// Renders `frame_count` frames without a frame cap while the camera circles
+// around the scene, then prints a summary. The camera path only depends on the
+// frame number, so runs on different machines can be compared.
+pub fn run_benchmark(
+    engine: &mut Engine,
+    event_pump: &mut EventPump,
+    frame_count: usize,
+    scene_center: &Vector3D,
+    scene_radius: f32,
+    csv_path: Option<&String>,
+) {
+    let mut records: Vec<FrameRecord> = Vec::with_capacity(frame_count);
+
+    for frame in 0..frame_count {
+        // Stop early when the window is closed
+        if event_pump
+            .poll_iter()
+            .any(|event| matches!(event, Event::Quit { .. }))
+        {
+            break;
+        }
+
+        let (position, target) = camera_path(frame, frame_count, scene_center, scene_radius);
+        engine.look_at(position, &target);
+
+        let frame_start = Instant::now();
+        engine.render_scene();
+        engine.present();
+        records.push(FrameRecord {
+            frame_time: frame_start.elapsed(),
+            stats: engine.stats().clone(),
+        });
+    }
+
+    if records.is_empty() {
+        println!("No frames rendered");
+        return;
+    }
+
+    let (size_x, size_y) = engine.size();
+    print_summary(&records, size_x, size_y);
+
+    if let Some(path) = csv_path {
+        match write_csv(path, &records) {
+            Ok(()) => println!("Wrote frame timings to {}", path),
+            Err(err) => eprintln!("Failed to write {}: {}", path, err),
+        }
+    }
+}
+
+// One full circle around the scene, going up and down twice. Returns the camera
+// position and the point it looks at.
+fn camera_path(
+    frame: usize,
+    frame_count: usize,
+    scene_center: &Vector3D,
+    scene_radius: f32,
+) -> (Vector3D, Vector3D) {
+    let t = frame as f32 / frame_count as f32;
+    let angle = t * TAU;
+    let distance = scene_radius * 2.5;
+
+    // Starts in front of the scene, looking down +z like the engine does
+    let offset = Vector3D::from_coords(
+        angle.sin() * distance,
+        (angle * 2.0).sin() * scene_radius * 0.5,
+        -angle.cos() * distance,
+    );
+
+    (scene_center + offset, *scene_center)
+}
+
+fn print_summary(records: &[FrameRecord], size_x: u32, size_y: u32) {
+    let frame_count = records.len();
+    let average = |stage: fn(&FrameRecord) -> Duration| {
+        records.iter().map(stage).sum::<Duration>() / frame_count as u32
+    };
+
+    let mut frame_times: Vec<Duration> = records.iter().map(|record| record.frame_time).collect();
+    frame_times.sort();
+    let p99_index = ((frame_count as f32 * 0.99).ceil() as usize).clamp(1, frame_count) - 1;
+    let average_frame_time = average(|record| record.frame_time);
+
+    println!("Rendered {} frames at {}x{}", frame_count, size_x, size_y);
+    println!(
+        "Frame time: min {:.3} ms, avg {:.3} ms, p99 {:.3} ms ({:.1} FPS)",
+        milliseconds(frame_times[0]),
+        milliseconds(average_frame_time),
+        milliseconds(frame_times[p99_index]),
+        1.0 / average_frame_time.as_secs_f64()
+    );
+    println!(
+        "Stage averages: transform {:.3} ms, shade {:.3} ms, clip {:.3} ms, sort {:.3} ms, raster {:.3} ms, present {:.3} ms",
+        milliseconds(average(|record| record.stats.transform_time)),
+        milliseconds(average(|record| record.stats.shade_time)),
+        milliseconds(average(|record| record.stats.clip_time)),
+        milliseconds(average(|record| record.stats.sort_time)),
+        milliseconds(average(|record| record.stats.raster_time)),
+        milliseconds(average(|record| record.stats.present_time)),
+    );
+
+    let triangles_in: usize = records.iter().map(|record| record.stats.triangles_in).sum();
+    let triangles_out: usize = records
+        .iter()
+        .map(|record| record.stats.triangles_out)
+        .sum();
+    println!(
+        "Triangles per frame: {} in, {} out",
+        triangles_in / frame_count,
+        triangles_out / frame_count
+    );
+}
+
+fn write_csv(path: &str, records: &[FrameRecord]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(
+        file,
+        "frame,frame_ms,transform_ms,shade_ms,clip_ms,sort_ms,raster_ms,present_ms,triangles_in,triangles_out"
+    )?;
+
+    for (frame, record) in records.iter().enumerate() {
+        let stats = &record.stats;
+        writeln!(
+            file,
+            "{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{},{}",
+            frame,
+            milliseconds(record.frame_time),
+            milliseconds(stats.transform_time),
+            milliseconds(stats.shade_time),
+            milliseconds(stats.clip_time),
+            milliseconds(stats.sort_time),
+            milliseconds(stats.raster_time),
+            milliseconds(stats.present_time),
+            stats.triangles_in,
+            stats.triangles_out
+        )?;
+    }
+
+    Ok(())
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -1,7 +1,34 @@
+pub mod aabb;
+pub mod application;
+pub mod bvh;
+pub mod camera;
 pub mod engine;
 pub mod files;
+pub mod font;
+pub mod frame_buffer;
+pub mod frustum;
+pub mod hud;
+pub mod indexed_mesh;
+pub mod input;
+pub mod lod;
 pub mod math;
 pub mod matrix_4x4;
 pub mod mesh;
+pub mod quaternion;
+pub mod ray;
+pub mod recording;
+pub mod renderer;
+pub mod scene_object;
+pub mod simplify;
+pub mod sprite;
+pub mod subdivision;
+#[cfg(test)]
+mod test_helpers;
+pub mod timestep;
 pub mod triangle;
+pub mod validation;
+pub mod vector_2d;
 pub mod vector_3d;
+pub mod vector_4d;
+mod vector_ops;
+pub mod vertex_batch;
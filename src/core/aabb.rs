@@ -0,0 +1,237 @@
+use super::{triangle::Triangle, vector_3d::Vector3D};
+
+// Axis aligned bounding box
+pub struct Aabb {
+    pub min: Vector3D,
+    pub max: Vector3D,
+}
+
+impl Aabb {
+    // An inverted box that grows to fit the first point added to it
+    pub fn new() -> Aabb {
+        Aabb {
+            min: Vector3D::from_coords(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vector3D::from_coords(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_triangle(triangle: &Triangle) -> Aabb {
+        let mut aabb = Aabb::new();
+        for vector in &triangle.vectors {
+            aabb.grow(vector);
+        }
+        aabb
+    }
+
+    pub fn grow(&mut self, point: &Vector3D) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.min.z = self.min.z.min(point.z);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+        self.max.z = self.max.z.max(point.z);
+    }
+
+    pub fn grow_aabb(&mut self, other: &Aabb) {
+        if other.is_empty() {
+            return;
+        }
+        self.grow(&other.min);
+        self.grow(&other.max);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn center(&self) -> Vector3D {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let size = self.max - self.min;
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.min.x <= other.min.x
+            && self.max.x >= other.max.x
+            && self.min.y <= other.min.y
+            && self.max.y >= other.max.y
+            && self.min.z <= other.min.z
+            && self.max.z >= other.max.z
+    }
+
+    // Squared distance from the point to the box, 0.0 when the point is inside
+    pub fn distance_squared(&self, point: &Vector3D) -> f32 {
+        let dx = (self.min.x - point.x).max(0.0).max(point.x - self.max.x);
+        let dy = (self.min.y - point.y).max(0.0).max(point.y - self.max.y);
+        let dz = (self.min.z - point.z).max(0.0).max(point.z - self.max.z);
+        dx * dx + dy * dy + dz * dz
+    }
+
+    // Slab test, returns the distance along the ray where it enters the box
+    pub fn intersect_ray(
+        &self,
+        origin: &Vector3D,
+        inverse_direction: &Vector3D,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let (x_min, x_max) = slab(self.min.x, self.max.x, origin.x, inverse_direction.x);
+        let (y_min, y_max) = slab(self.min.y, self.max.y, origin.y, inverse_direction.y);
+        let (z_min, z_max) = slab(self.min.z, self.max.z, origin.z, inverse_direction.z);
+        let t_min = x_min.max(y_min).max(z_min);
+        let t_max = x_max.min(y_max).min(z_max);
+
+        if t_max >= t_min.max(0.0) && t_min < max_distance {
+            Some(t_min.max(0.0))
+        } else {
+            None
+        }
+    }
+}
+
+// Distances along the ray where it enters and exits the space between two parallel planes
+fn slab(min: f32, max: f32, origin: f32, inverse_direction: f32) -> (f32, f32) {
+    if inverse_direction.is_infinite() {
+        // The ray runs parallel to the planes, so it is either always or never between them
+        return if origin >= min && origin <= max {
+            (f32::NEG_INFINITY, f32::INFINITY)
+        } else {
+            (f32::INFINITY, f32::NEG_INFINITY)
+        };
+    }
+
+    let t1 = (min - origin) * inverse_direction;
+    let t2 = (max - origin) * inverse_direction;
+    (t1.min(t2), t1.max(t2))
+}
+
+impl Clone for Aabb {
+    fn clone(&self) -> Aabb {
+        Aabb {
+            min: self.min,
+            max: self.max,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_helpers::vector;
+    use proptest::prelude::*;
+
+    fn unit_box() -> Aabb {
+        let mut aabb = Aabb::new();
+        aabb.grow(&Vector3D::from_coords(-1.0, -1.0, -1.0));
+        aabb.grow(&Vector3D::from_coords(1.0, 1.0, 1.0));
+        aabb
+    }
+
+    fn inverse(direction: Vector3D) -> Vector3D {
+        Vector3D::from_coords(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z)
+    }
+
+    #[test]
+    fn ray_enters_box_at_the_near_face() {
+        let origin = Vector3D::from_coords(0.5, 0.5, -5.0);
+        let direction = inverse(Vector3D::from_coords(0.0, 0.0, 1.0));
+
+        assert_eq!(
+            unit_box().intersect_ray(&origin, &direction, f32::INFINITY),
+            Some(4.0)
+        );
+        // Starting inside counts as entering right away
+        let inside = Vector3D::from_coords(0.0, 0.0, 0.0);
+        assert_eq!(
+            unit_box().intersect_ray(&inside, &direction, f32::INFINITY),
+            Some(0.0)
+        );
+        // Hits further away than a known hit are skipped
+        assert_eq!(unit_box().intersect_ray(&origin, &direction, 3.0), None);
+    }
+
+    #[test]
+    fn ray_misses_box() {
+        let direction = inverse(Vector3D::from_coords(0.0, 0.0, 1.0));
+
+        // Parallel to the x slabs, but outside them
+        let beside = Vector3D::from_coords(2.0, 0.0, -5.0);
+        assert_eq!(
+            unit_box().intersect_ray(&beside, &direction, f32::INFINITY),
+            None
+        );
+        // The box is behind the ray
+        let past = Vector3D::from_coords(0.0, 0.0, 5.0);
+        assert_eq!(
+            unit_box().intersect_ray(&past, &direction, f32::INFINITY),
+            None
+        );
+    }
+
+    #[test]
+    fn empty_box_does_not_grow_others() {
+        let mut aabb = unit_box();
+        aabb.grow_aabb(&Aabb::new());
+
+        assert!(Aabb::new().is_empty());
+        assert_eq!(aabb.surface_area(), 24.0);
+        assert!(aabb.contains(&unit_box()));
+    }
+
+    proptest! {
+        #[test]
+        fn distance_squared_matches_clamped_point(point in vector()) {
+            let clamped = Vector3D::from_coords(
+                point.x.clamp(-1.0, 1.0),
+                point.y.clamp(-1.0, 1.0),
+                point.z.clamp(-1.0, 1.0),
+            );
+            let offset = point - clamped;
+            let expected = offset.x * offset.x + offset.y * offset.y + offset.z * offset.z;
+
+            prop_assert!((unit_box().distance_squared(&point) - expected).abs() <= 1e-3);
+        }
+
+        #[test]
+        fn overlapping_boxes_share_a_point(a in vector(), b in vector(), c in vector(), d in vector()) {
+            let mut first = Aabb::new();
+            first.grow(&a);
+            first.grow(&b);
+            let mut second = Aabb::new();
+            second.grow(&c);
+            second.grow(&d);
+
+            // The overlap is a box of its own when the boxes overlap
+            let overlap_min = Vector3D::from_coords(
+                first.min.x.max(second.min.x),
+                first.min.y.max(second.min.y),
+                first.min.z.max(second.min.z),
+            );
+            let overlap_max = Vector3D::from_coords(
+                first.max.x.min(second.max.x),
+                first.max.y.min(second.max.y),
+                first.max.z.min(second.max.z),
+            );
+            let has_overlap = overlap_min.x <= overlap_max.x
+                && overlap_min.y <= overlap_max.y
+                && overlap_min.z <= overlap_max.z;
+
+            prop_assert_eq!(first.overlaps(&second), has_overlap);
+            prop_assert_eq!(second.overlaps(&first), has_overlap);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use sdl2::event::Event;
+
+use super::{engine::Engine, input::Action};
+
+// What runs on top of the engine, see `Engine::run`. The engine owns the window,
+// the main loop, input and timing, and calls these in order every frame: input
+// for every event, fixed updates for every step that has passed, then update.
+// Only `on_user_update` has to be implemented.
+pub trait Application {
+    // Called once before the first frame, return false to stop right away
+    fn on_user_create(&mut self, _engine: &mut Engine) -> bool {
+        true
+    }
+
+    // Called for every input event, with the actions it just triggered. Held
+    // actions can be checked any time with `engine.input().is_held(...)`.
+    fn on_user_input(&mut self, _engine: &mut Engine, _event: &Event, _actions: &[Action]) {}
+
+    // Called a fixed number of times per second whatever the frame rate, with the
+    // seconds per step. Camera movement done here is interpolated between steps.
+    fn on_fixed_update(&mut self, _engine: &mut Engine, _step: f32) {}
+
+    // Called every frame with the seconds since the previous one, before the frame
+    // is presented. Draws the frame, return false to stop.
+    fn on_user_update(&mut self, engine: &mut Engine, elapsed_time: f32) -> bool;
+
+    // Called once after the last frame
+    fn on_user_destroy(&mut self, _engine: &mut Engine) {}
+}
@@ -0,0 +1,658 @@
+use super::{
+    aabb::Aabb,
+    frustum::{Containment, Frustum},
+    math::{closest_point_on_triangle, ray_triangle_intersection, vector_len},
+    mesh::Mesh,
+    ray::Ray,
+    vector_3d::Vector3D,
+};
+
+// Number of buckets the surface area heuristic evaluates per axis
+const SAH_BINS: usize = 12;
+// Nodes with this many triangles or less are never split
+const MAX_LEAF_SIZE: usize = 2;
+
+pub struct BvhNode {
+    pub bounds: Aabb,
+    // Index of the left child (the right child follows it) for inner nodes,
+    // index of the first triangle in `triangle_indices` for leaves
+    first: usize,
+    // Number of triangles, 0 for inner nodes
+    count: usize,
+}
+
+impl BvhNode {
+    pub fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+pub struct RayHit {
+    pub triangle: usize,
+    pub distance: f32,
+    pub point: Vector3D,
+    // Barycentric coordinates of the hit inside the triangle
+    pub u: f32,
+    pub v: f32,
+}
+
+pub struct NearestPoint {
+    pub triangle: usize,
+    pub point: Vector3D,
+    pub distance: f32,
+}
+
+// Bounding volume hierarchy over the triangles of a mesh, built with the
+// surface area heuristic. Triangles are referenced by their index in the mesh.
+pub struct Bvh {
+    pub nodes: Vec<BvhNode>,
+    triangle_indices: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(mesh: &Mesh) -> Bvh {
+        let triangle_count = mesh.triangles.len();
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(triangle_count * 2),
+            triangle_indices: (0..triangle_count).collect(),
+        };
+
+        let triangle_bounds: Vec<Aabb> = mesh.triangles.iter().map(Aabb::from_triangle).collect();
+        let centroids: Vec<Vector3D> = triangle_bounds.iter().map(Aabb::center).collect();
+
+        bvh.nodes.push(BvhNode {
+            bounds: Aabb::new(),
+            first: 0,
+            count: triangle_count,
+        });
+        bvh.update_bounds(0, &triangle_bounds);
+        if triangle_count > 0 {
+            bvh.subdivide(0, &triangle_bounds, &centroids);
+        }
+
+        bvh
+    }
+
+    fn update_bounds(&mut self, node_index: usize, triangle_bounds: &[Aabb]) {
+        let node = &self.nodes[node_index];
+        let mut bounds = Aabb::new();
+        for &triangle in &self.triangle_indices[node.first..node.first + node.count] {
+            bounds.grow_aabb(&triangle_bounds[triangle]);
+        }
+        self.nodes[node_index].bounds = bounds;
+    }
+
+    fn subdivide(&mut self, node_index: usize, triangle_bounds: &[Aabb], centroids: &[Vector3D]) {
+        let first = self.nodes[node_index].first;
+        let count = self.nodes[node_index].count;
+        if count <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        let Some((axis, split_position, split_cost)) =
+            self.find_best_split(first, count, triangle_bounds, centroids)
+        else {
+            return;
+        };
+
+        // Stop when splitting is more expensive than testing every triangle
+        let leaf_cost = count as f32 * self.nodes[node_index].bounds.surface_area();
+        if split_cost >= leaf_cost {
+            return;
+        }
+
+        // Partition the triangles in place around the split position
+        let mut i = first;
+        let mut j = first + count;
+        while i < j {
+            if axis_value(&centroids[self.triangle_indices[i]], axis) < split_position {
+                i += 1;
+            } else {
+                j -= 1;
+                self.triangle_indices.swap(i, j);
+            }
+        }
+
+        let left_count = i - first;
+        if left_count == 0 || left_count == count {
+            return;
+        }
+
+        let left_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds: Aabb::new(),
+            first,
+            count: left_count,
+        });
+        self.nodes.push(BvhNode {
+            bounds: Aabb::new(),
+            first: i,
+            count: count - left_count,
+        });
+        self.nodes[node_index].first = left_index;
+        self.nodes[node_index].count = 0;
+
+        self.update_bounds(left_index, triangle_bounds);
+        self.update_bounds(left_index + 1, triangle_bounds);
+        self.subdivide(left_index, triangle_bounds, centroids);
+        self.subdivide(left_index + 1, triangle_bounds, centroids);
+    }
+
+    // Evaluates the surface area heuristic on evenly spaced bins along every axis
+    fn find_best_split(
+        &self,
+        first: usize,
+        count: usize,
+        triangle_bounds: &[Aabb],
+        centroids: &[Vector3D],
+    ) -> Option<(usize, f32, f32)> {
+        let triangles = &self.triangle_indices[first..first + count];
+        let mut centroid_bounds = Aabb::new();
+        for &triangle in triangles {
+            centroid_bounds.grow(&centroids[triangle]);
+        }
+
+        let mut best: Option<(usize, f32, f32)> = None;
+        for axis in 0..3 {
+            let min = axis_value(&centroid_bounds.min, axis);
+            let max = axis_value(&centroid_bounds.max, axis);
+            if max <= min {
+                continue;
+            }
+
+            let mut bins: Vec<(Aabb, usize)> = (0..SAH_BINS).map(|_| (Aabb::new(), 0)).collect();
+            let scale = SAH_BINS as f32 / (max - min);
+            for &triangle in triangles {
+                let bin = (((axis_value(&centroids[triangle], axis) - min) * scale) as usize)
+                    .min(SAH_BINS - 1);
+                bins[bin].0.grow_aabb(&triangle_bounds[triangle]);
+                bins[bin].1 += 1;
+            }
+
+            // Sweep from both sides to get the area and count left and right of every split
+            let mut left_area = [0.0; SAH_BINS - 1];
+            let mut left_count = [0; SAH_BINS - 1];
+            let mut right_area = [0.0; SAH_BINS - 1];
+            let mut right_count = [0; SAH_BINS - 1];
+            let mut left_box = Aabb::new();
+            let mut right_box = Aabb::new();
+            let mut left_sum = 0;
+            let mut right_sum = 0;
+            for i in 0..SAH_BINS - 1 {
+                left_sum += bins[i].1;
+                left_box.grow_aabb(&bins[i].0);
+                left_count[i] = left_sum;
+                left_area[i] = left_box.surface_area();
+
+                right_sum += bins[SAH_BINS - 1 - i].1;
+                right_box.grow_aabb(&bins[SAH_BINS - 1 - i].0);
+                right_count[SAH_BINS - 2 - i] = right_sum;
+                right_area[SAH_BINS - 2 - i] = right_box.surface_area();
+            }
+
+            for i in 0..SAH_BINS - 1 {
+                let cost =
+                    left_count[i] as f32 * left_area[i] + right_count[i] as f32 * right_area[i];
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((axis, min + (i + 1) as f32 / scale, cost));
+                }
+            }
+        }
+
+        best
+    }
+
+    // Puts the triangles of `mesh` in the order of the leaves, so every subtree
+    // covers a range of triangles that follow each other. The mesh must be the
+    // one the BVH was built for, triangle indices refer to the new order after.
+    pub fn reorder_mesh(&mut self, mesh: &mut Mesh) {
+        mesh.triangles = self
+            .triangle_indices
+            .iter()
+            .map(|&triangle| mesh.triangles[triangle].clone())
+            .collect();
+        self.triangle_indices = (0..mesh.triangles.len()).collect();
+    }
+
+    // Recomputes the node bounds after the vertices of the mesh have moved.
+    // The tree structure is kept, so the mesh must have the same triangles.
+    pub fn refit(&mut self, mesh: &Mesh) {
+        // Children are always stored after their parent, so walking backwards
+        // visits every child before its parent
+        for node_index in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[node_index];
+            let mut bounds = Aabb::new();
+            if node.is_leaf() {
+                for &triangle in &self.triangle_indices[node.first..node.first + node.count] {
+                    bounds.grow_aabb(&Aabb::from_triangle(&mesh.triangles[triangle]));
+                }
+            } else {
+                bounds.grow_aabb(&self.nodes[node.first].bounds);
+                bounds.grow_aabb(&self.nodes[node.first + 1].bounds);
+            }
+            self.nodes[node_index].bounds = bounds;
+        }
+    }
+
+    pub fn intersect_ray(&self, mesh: &Mesh, ray: &Ray) -> Option<RayHit> {
+        if self.triangle_indices.is_empty() {
+            return None;
+        }
+
+        let inverse_direction = Vector3D::from_coords(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        let mut closest: Option<RayHit> = None;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let max_distance = closest.as_ref().map_or(f32::INFINITY, |hit| hit.distance);
+            if node
+                .bounds
+                .intersect_ray(&ray.origin, &inverse_direction, max_distance)
+                .is_none()
+            {
+                continue;
+            }
+
+            if !node.is_leaf() {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+                continue;
+            }
+
+            for &triangle in &self.triangle_indices[node.first..node.first + node.count] {
+                let vectors = &mesh.triangles[triangle].vectors;
+                if let Some((distance, u, v)) = ray_triangle_intersection(
+                    &ray.origin,
+                    &ray.direction,
+                    &vectors[0],
+                    &vectors[1],
+                    &vectors[2],
+                ) {
+                    if distance < max_distance
+                        && closest.as_ref().is_none_or(|hit| distance < hit.distance)
+                    {
+                        closest = Some(RayHit {
+                            triangle,
+                            distance,
+                            point: ray.at(distance),
+                            u,
+                            v,
+                        });
+                    }
+                }
+            }
+        }
+
+        closest
+    }
+
+    // Indices of the triangles whose bounds overlap the box
+    pub fn query_aabb(&self, mesh: &Mesh, aabb: &Aabb) -> Vec<usize> {
+        let mut result = vec![];
+        if self.triangle_indices.is_empty() {
+            return result;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !node.bounds.overlaps(aabb) {
+                continue;
+            }
+
+            if aabb.contains(&node.bounds) {
+                self.collect_triangles(node_index, &mut result);
+            } else if node.is_leaf() {
+                result.extend(
+                    self.triangle_indices[node.first..node.first + node.count]
+                        .iter()
+                        .filter(|triangle| {
+                            Aabb::from_triangle(&mesh.triangles[**triangle]).overlaps(aabb)
+                        }),
+                );
+            } else {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+            }
+        }
+
+        result
+    }
+
+    // Indices of the triangles in leaves that are (partially) inside the frustum,
+    // in the order they are stored in the leaves. This is conservative: triangles
+    // in a visible leaf can still be outside.
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<usize> {
+        let mut result = vec![];
+        if self.triangle_indices.is_empty() {
+            return result;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            match frustum.classify_aabb(&node.bounds) {
+                Containment::Outside => {}
+                Containment::Inside => self.collect_triangles(node_index, &mut result),
+                Containment::Intersecting => {
+                    if node.is_leaf() {
+                        result.extend_from_slice(
+                            &self.triangle_indices[node.first..node.first + node.count],
+                        );
+                    } else {
+                        // Left first, so leaves come out in the order they are stored
+                        stack.push(node.first + 1);
+                        stack.push(node.first);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn nearest_point(&self, mesh: &Mesh, point: &Vector3D) -> Option<NearestPoint> {
+        if self.triangle_indices.is_empty() {
+            return None;
+        }
+
+        let mut nearest: Option<NearestPoint> = None;
+        let mut best_distance_squared = f32::INFINITY;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.bounds.distance_squared(point) >= best_distance_squared {
+                continue;
+            }
+
+            if !node.is_leaf() {
+                // Visit the closest child first, so the other one is more likely to be skipped
+                let left = node.first;
+                let right = node.first + 1;
+                if self.nodes[left].bounds.distance_squared(point)
+                    < self.nodes[right].bounds.distance_squared(point)
+                {
+                    stack.push(right);
+                    stack.push(left);
+                } else {
+                    stack.push(left);
+                    stack.push(right);
+                }
+                continue;
+            }
+
+            for &triangle in &self.triangle_indices[node.first..node.first + node.count] {
+                let vectors = &mesh.triangles[triangle].vectors;
+                let closest =
+                    closest_point_on_triangle(point, &vectors[0], &vectors[1], &vectors[2]);
+                let distance = vector_len(&(closest - point));
+                if distance * distance < best_distance_squared {
+                    best_distance_squared = distance * distance;
+                    nearest = Some(NearestPoint {
+                        triangle,
+                        point: closest,
+                        distance,
+                    });
+                }
+            }
+        }
+
+        nearest
+    }
+
+    fn collect_triangles(&self, node_index: usize, result: &mut Vec<usize>) {
+        let mut stack = vec![node_index];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.is_leaf() {
+                result
+                    .extend_from_slice(&self.triangle_indices[node.first..node.first + node.count]);
+            } else {
+                stack.push(node.first + 1);
+                stack.push(node.first);
+            }
+        }
+    }
+}
+
+fn axis_value(vector: &Vector3D, axis: usize) -> f32 {
+    match axis {
+        0 => vector.x,
+        1 => vector.y,
+        _ => vector.z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{frustum::Plane, test_helpers::vector, triangle::Triangle};
+    use proptest::prelude::*;
+
+    // Mixes small triangles with ones that span most of the scene
+    fn triangle() -> impl Strategy<Value = [Vector3D; 3]> {
+        (vector(), vector(), vector(), 0.01f32..1.0)
+            .prop_map(|(center, a, b, size)| [center, center + a * size, center + b * size])
+    }
+
+    fn triangles() -> impl Strategy<Value = Vec<[Vector3D; 3]>> {
+        prop::collection::vec(triangle(), 1..80)
+    }
+
+    // Origin and direction
+    fn ray() -> impl Strategy<Value = (Vector3D, Vector3D)> {
+        (vector(), vector()).prop_filter("direction is too short", |(_, direction)| {
+            vector_len(direction) > 0.1
+        })
+    }
+
+    fn to_mesh(triangles: &[[Vector3D; 3]]) -> Mesh {
+        Mesh {
+            triangles: triangles
+                .iter()
+                .map(|vectors| Triangle::new_from_vectors(*vectors))
+                .collect(),
+            polygons: vec![],
+        }
+    }
+
+    fn brute_force_ray(mesh: &Mesh, ray: &Ray) -> Option<f32> {
+        mesh.triangles
+            .iter()
+            .filter_map(|triangle| {
+                let vectors = &triangle.vectors;
+                ray_triangle_intersection(
+                    &ray.origin,
+                    &ray.direction,
+                    &vectors[0],
+                    &vectors[1],
+                    &vectors[2],
+                )
+            })
+            .map(|(distance, _, _)| distance)
+            .min_by(f32::total_cmp)
+    }
+
+    fn brute_force_nearest(mesh: &Mesh, point: &Vector3D) -> f32 {
+        mesh.triangles
+            .iter()
+            .map(|triangle| {
+                let vectors = &triangle.vectors;
+                let closest =
+                    closest_point_on_triangle(point, &vectors[0], &vectors[1], &vectors[2]);
+                vector_len(&(closest - point))
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    fn check_ray(bvh: &Bvh, mesh: &Mesh, ray: &Ray) -> Result<(), TestCaseError> {
+        let hit = bvh.intersect_ray(mesh, ray);
+        match (&hit, brute_force_ray(mesh, ray)) {
+            (None, None) => {}
+            (Some(hit), Some(distance)) => {
+                prop_assert!((hit.distance - distance).abs() <= 1e-3 * distance.max(1.0));
+                prop_assert!(hit.point.approx_eq(&ray.at(hit.distance), 1e-3));
+            }
+            (hit, distance) => prop_assert!(
+                false,
+                "bvh hit {:?}, brute force hit {:?}",
+                hit.as_ref().map(|hit| hit.distance),
+                distance
+            ),
+        }
+        Ok(())
+    }
+
+    fn check_nearest(bvh: &Bvh, mesh: &Mesh, point: &Vector3D) -> Result<(), TestCaseError> {
+        let nearest = bvh.nearest_point(mesh, point).unwrap();
+        let distance = brute_force_nearest(mesh, point);
+        prop_assert!((nearest.distance - distance).abs() <= 1e-3 * distance.max(1.0));
+
+        // The point is on the triangle that is reported
+        let vectors = &mesh.triangles[nearest.triangle].vectors;
+        let on_triangle = closest_point_on_triangle(point, &vectors[0], &vectors[1], &vectors[2]);
+        prop_assert!(nearest.point.approx_eq(&on_triangle, 1e-3));
+        Ok(())
+    }
+
+    fn check_aabb(bvh: &Bvh, mesh: &Mesh, aabb: &Aabb) -> Result<(), TestCaseError> {
+        let mut result = bvh.query_aabb(mesh, aabb);
+        result.sort_unstable();
+        let expected: Vec<usize> = (0..mesh.triangles.len())
+            .filter(|triangle| Aabb::from_triangle(&mesh.triangles[*triangle]).overlaps(aabb))
+            .collect();
+        prop_assert_eq!(result, expected);
+        Ok(())
+    }
+
+    fn aabb_from(a: &Vector3D, b: &Vector3D) -> Aabb {
+        let mut aabb = Aabb::new();
+        aabb.grow(a);
+        aabb.grow(b);
+        aabb
+    }
+
+    #[test]
+    fn empty_mesh_has_no_hits() {
+        let mesh = Mesh {
+            triangles: vec![],
+            polygons: vec![],
+        };
+        let bvh = Bvh::new(&mesh);
+        let origin = Vector3D::from_coords(0.0, 0.0, 0.0);
+
+        assert!(bvh
+            .intersect_ray(
+                &mesh,
+                &Ray::new(origin, Vector3D::from_coords(0.0, 0.0, 1.0))
+            )
+            .is_none());
+        assert!(bvh.nearest_point(&mesh, &origin).is_none());
+        assert!(bvh
+            .query_aabb(&mesh, &aabb_from(&origin, &origin))
+            .is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn intersect_ray_matches_brute_force(triangles in triangles(), (origin, direction) in ray()) {
+            let mesh = to_mesh(&triangles);
+            check_ray(&Bvh::new(&mesh), &mesh, &Ray::new(origin, direction))?;
+        }
+
+        #[test]
+        fn nearest_point_matches_brute_force(triangles in triangles(), point in vector()) {
+            let mesh = to_mesh(&triangles);
+            check_nearest(&Bvh::new(&mesh), &mesh, &point)?;
+        }
+
+        #[test]
+        fn query_aabb_matches_brute_force(triangles in triangles(), a in vector(), b in vector()) {
+            let mesh = to_mesh(&triangles);
+            check_aabb(&Bvh::new(&mesh), &mesh, &aabb_from(&a, &b))?;
+        }
+
+        #[test]
+        fn query_frustum_matches_brute_force(
+            triangles in triangles(),
+            planes in prop::collection::vec((vector(), vector()), 1..7),
+        ) {
+            prop_assume!(planes.iter().all(|(_, normal)| vector_len(normal) > 0.1));
+            let mesh = to_mesh(&triangles);
+            let frustum = Frustum::from_planes(
+                planes
+                    .iter()
+                    .map(|(point, normal)| Plane::from_point_normal(point, normal))
+                    .collect(),
+            );
+
+            let mut result = Bvh::new(&mesh).query_frustum(&frustum);
+            result.sort_unstable();
+            let before_dedup = result.len();
+            result.dedup();
+            prop_assert_eq!(result.len(), before_dedup);
+
+            for (index, triangle) in mesh.triangles.iter().enumerate() {
+                let returned = result.binary_search(&index).is_ok();
+                // Triangles whose own bounds are not outside are always returned
+                if frustum.classify_aabb(&Aabb::from_triangle(triangle)) != Containment::Outside {
+                    prop_assert!(returned, "triangle {} is missing", index);
+                }
+                // Triangles that are left out are completely behind one of the planes
+                if !returned {
+                    let behind_a_plane = frustum.planes.iter().any(|plane| {
+                        triangle
+                            .vectors
+                            .iter()
+                            .all(|vector| plane.signed_distance(vector) < 0.0)
+                    });
+                    prop_assert!(behind_a_plane);
+                }
+            }
+        }
+
+        #[test]
+        fn reordered_mesh_matches_brute_force(
+            triangles in triangles(),
+            (origin, direction) in ray(),
+            point in vector(),
+        ) {
+            let mut mesh = to_mesh(&triangles);
+            let mut bvh = Bvh::new(&mesh);
+            bvh.reorder_mesh(&mut mesh);
+
+            check_ray(&bvh, &mesh, &Ray::new(origin, direction))?;
+            check_nearest(&bvh, &mesh, &point)?;
+        }
+
+        #[test]
+        fn refit_matches_brute_force_after_moving(
+            (mesh, moved) in (1usize..80).prop_flat_map(|count| {
+                (
+                    prop::collection::vec(triangle(), count),
+                    prop::collection::vec(triangle(), count),
+                )
+            }),
+            (origin, direction) in ray(),
+            point in vector(),
+            a in vector(),
+            b in vector(),
+        ) {
+            let mut bvh = Bvh::new(&to_mesh(&mesh));
+
+            // Every triangle moves somewhere else, the tree structure stays the same
+            let moved = to_mesh(&moved);
+            bvh.refit(&moved);
+
+            check_ray(&bvh, &moved, &Ray::new(origin, direction))?;
+            check_nearest(&bvh, &moved, &point)?;
+            check_aabb(&bvh, &moved, &aabb_from(&a, &b))?;
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use super::{math::vector_len, matrix_4x4::Matrix4X4, quaternion::Quaternion, vector_3d::Vector3D};
+
+// Limits for zooming, in degrees of field of view
+const MIN_FOV: f32 = 10.0;
+const MAX_FOV: f32 = 150.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CameraMode {
+    // Moves freely and turns around its own position
+    Fly,
+    // Turns around `target`, staying `distance` away from it
+    Orbit,
+}
+
+// How far a camera moved and turned during one update step
+#[derive(Clone)]
+pub struct CameraMotion {
+    pub offset: Vector3D,
+    // Turns the old orientation into the new one
+    pub rotation: Quaternion,
+}
+
+impl Default for CameraMotion {
+    // Standing still
+    fn default() -> Self {
+        CameraMotion {
+            offset: Vector3D::new(),
+            rotation: Quaternion::from_identity(),
+        }
+    }
+}
+
+// A point of view: where the camera is, where it looks and how it projects the
+// world onto the screen
+#[derive(Clone)]
+pub struct Camera {
+    pub position: Vector3D,
+    // Turns the view space axes into the world space ones. There are no angles to
+    // run into gimbal lock, so the camera can turn all the way over and roll.
+    pub orientation: Quaternion,
+    pub projection: Projection,
+    // Vertical field of view in degrees, for perspective projection
+    pub fov: f32,
+    // How many world units are visible vertically, for orthographic projection
+    pub view_height: f32,
+    pub near_plane: f32,
+    pub far_plane: f32,
+    // Height divided by width. `None` follows the frame that is rendered to.
+    pub aspect_ratio: Option<f32>,
+    pub mode: CameraMode,
+    pub target: Vector3D,
+    pub distance: f32,
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {
+            position: Vector3D::new(),
+            orientation: Quaternion::from_identity(),
+            projection: Projection::Perspective,
+            fov: 90.0,
+            view_height: 4.0,
+            near_plane: 0.1,
+            far_plane: 1000.0,
+            aspect_ratio: None,
+            mode: CameraMode::Fly,
+            target: Vector3D::from_coords(0.0, 0.0, 1.0),
+            distance: 1.0,
+        }
+    }
+
+    pub fn from_orthographic(view_height: f32) -> Camera {
+        Camera {
+            projection: Projection::Orthographic,
+            view_height,
+            ..Camera::new()
+        }
+    }
+
+    pub fn look_direction(&self) -> Vector3D {
+        &self.orientation.to_matrix() * &Vector3D::from_coords(0.0, 0.0, 1.0)
+    }
+
+    pub fn up_direction(&self) -> Vector3D {
+        &self.orientation.to_matrix() * &Vector3D::from_coords(0.0, 1.0, 0.0)
+    }
+
+    // The screen is flipped horizontally, so +x in view space is on the left
+    pub fn left_direction(&self) -> Vector3D {
+        &self.orientation.to_matrix() * &Vector3D::from_coords(1.0, 0.0, 0.0)
+    }
+
+    // Turns the camera towards `target`, level with the horizon
+    pub fn look_at(&mut self, target: &Vector3D) {
+        let direction = (target - self.position).from_normalise();
+        let yaw = (-direction.x).atan2(direction.z);
+        let pitch = (-direction.y).clamp(-1.0, 1.0).asin();
+        self.orientation = Quaternion::from_euler(pitch, yaw, 0.0);
+    }
+
+    // Turns by the given angles in radians around the camera's own up and left
+    // axes, so pitching past straight up turns the camera over. In orbit mode the
+    // camera moves around the target instead of turning in place.
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.turn(&Quaternion::from_euler(pitch, yaw, 0.0));
+    }
+
+    // Tilts the camera around its look direction, in radians
+    pub fn roll(&mut self, angle: f32) {
+        self.turn(&Quaternion::from_axis_angle(
+            &Vector3D::from_coords(0.0, 0.0, 1.0),
+            angle,
+        ));
+    }
+
+    // `rotation` is in view space, so it is applied before the orientation
+    fn turn(&mut self, rotation: &Quaternion) {
+        // Renormalising keeps rounding errors from building up over many turns
+        self.orientation = (rotation * &self.orientation).normalise();
+        self.update_orbit();
+    }
+
+    // Moves the camera, and in orbit mode the target along with it
+    pub fn translate(&mut self, offset: &Vector3D) {
+        self.position += offset;
+        if self.mode == CameraMode::Orbit {
+            self.target += offset;
+        }
+    }
+
+    // Moves sideways and up or down, following the mouse. Closer to the target
+    // the camera moves slower.
+    pub fn pan(&mut self, rel_x: f32, rel_y: f32) {
+        let left = self.left_direction();
+        let up = self.up_direction();
+
+        let speed = self.distance * 0.002;
+        self.translate(&((left * rel_x + up * rel_y) * speed));
+    }
+
+    // Switches between flying and orbiting around `target` from the current position
+    pub fn toggle_mode(&mut self) {
+        match self.mode {
+            CameraMode::Fly => {
+                self.set_orbit_target(self.target, vector_len(&(self.position - self.target)))
+            }
+            CameraMode::Orbit => self.mode = CameraMode::Fly,
+        }
+    }
+
+    // Starts orbiting around `target`, `distance` away from it in the direction
+    // the camera is now
+    pub fn set_orbit_target(&mut self, target: Vector3D, distance: f32) {
+        self.mode = CameraMode::Orbit;
+        self.target = target;
+        self.distance = distance.max(self.near_plane * 2.0);
+        self.look_at(&target);
+        self.update_orbit();
+    }
+
+    fn update_orbit(&mut self) {
+        if self.mode == CameraMode::Orbit {
+            self.position = self.target - self.look_direction() * self.distance;
+        }
+    }
+
+    pub fn view_matrix(&self) -> Matrix4X4 {
+        let up_vector = self.up_direction();
+        let target_vector = self.position + self.look_direction();
+
+        Matrix4X4::from_point_at(&self.position, &target_vector, &up_vector).quick_inverse()
+    }
+
+    // `frame_aspect_ratio` is used when the camera has no aspect ratio of its own
+    pub fn projection_matrix(&self, frame_aspect_ratio: f32) -> Matrix4X4 {
+        let aspect_ratio = self.aspect_ratio.unwrap_or(frame_aspect_ratio);
+
+        match self.projection {
+            Projection::Perspective => {
+                Matrix4X4::from_projection(self.fov, aspect_ratio, self.near_plane, self.far_plane)
+            }
+            Projection::Orthographic => Matrix4X4::from_orthographic(
+                self.view_height,
+                aspect_ratio,
+                self.near_plane,
+                self.far_plane,
+            ),
+        }
+    }
+
+    // Zooms in for positive steps and out for negative ones, like a mouse wheel.
+    // A perspective camera in orbit mode moves closer instead of narrowing its view.
+    pub fn zoom(&mut self, steps: f32) {
+        let factor = 0.9f32.powf(steps);
+        match (self.projection, self.mode) {
+            (Projection::Perspective, CameraMode::Fly) => {
+                self.fov = (self.fov * factor).clamp(MIN_FOV, MAX_FOV)
+            }
+            (Projection::Perspective, CameraMode::Orbit) => {
+                self.distance = (self.distance * factor).max(self.near_plane * 2.0);
+                self.update_orbit();
+            }
+            (Projection::Orthographic, _) => self.view_height *= factor,
+        }
+    }
+
+    pub fn motion_since(&self, previous: &Camera) -> CameraMotion {
+        CameraMotion {
+            offset: self.position - previous.position,
+            rotation: (&self.orientation * &previous.orientation.conjugate()).normalise(),
+        }
+    }
+
+    // The camera as it was `amount` of `motion` ago, 1 undoes all of it. Used to
+    // draw frames that fall between two update steps.
+    pub fn rewound(&self, motion: &CameraMotion, amount: f32) -> Camera {
+        let mut camera = self.clone();
+        camera.position -= motion.offset * amount;
+        let previous_orientation = &motion.rotation.conjugate() * &self.orientation;
+        camera.orientation = self.orientation.slerp(&previous_orientation, amount);
+        camera
+    }
+
+    pub fn toggle_projection(&mut self) {
+        self.projection = match self.projection {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Perspective,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::math::vector_dot_product;
+    use proptest::prelude::*;
+
+    #[test]
+    fn look_at_faces_target() {
+        let mut camera = Camera::new();
+        camera.position = Vector3D::from_coords(1.0, 2.0, 3.0);
+        camera.look_at(&Vector3D::from_coords(-2.0, 0.0, 5.0));
+
+        let expected = Vector3D::from_coords(-3.0, -2.0, 2.0).from_normalise();
+        assert!(camera.look_direction().approx_eq(&expected, 1e-5));
+    }
+
+    #[test]
+    fn pitching_past_straight_down_turns_over() {
+        let mut camera = Camera::new();
+        camera.rotate(0.0, std::f32::consts::FRAC_PI_2);
+        assert!(camera
+            .look_direction()
+            .approx_eq(&Vector3D::from_coords(0.0, -1.0, 0.0), 1e-5));
+
+        // Half a loop later the camera looks back, upside down
+        camera.rotate(0.0, std::f32::consts::FRAC_PI_2);
+        assert!(camera
+            .look_direction()
+            .approx_eq(&Vector3D::from_coords(0.0, 0.0, -1.0), 1e-5));
+        assert!(camera
+            .up_direction()
+            .approx_eq(&Vector3D::from_coords(0.0, -1.0, 0.0), 1e-5));
+    }
+
+    #[test]
+    fn roll_keeps_the_look_direction() {
+        let mut camera = Camera::new();
+        camera.look_at(&Vector3D::from_coords(1.0, 1.0, 1.0));
+        let look_direction = camera.look_direction();
+        camera.roll(1.0);
+
+        assert!(camera.look_direction().approx_eq(&look_direction, 1e-5));
+        let up = camera.up_direction();
+        assert!((up.y - 1.0).abs() > 0.1);
+        assert!(vector_dot_product(&up, &look_direction).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rewound_turns_part_of_the_way_back() {
+        let mut camera = Camera::new();
+        let previous = camera.clone();
+        camera.rotate(1.0, 0.0);
+        camera.translate(&Vector3D::from_coords(2.0, 0.0, 0.0));
+        let motion = camera.motion_since(&previous);
+
+        let mut halfway = previous.clone();
+        halfway.rotate(0.5, 0.0);
+        let rewound = camera.rewound(&motion, 0.5);
+        assert!(rewound
+            .look_direction()
+            .approx_eq(&halfway.look_direction(), 1e-5));
+        assert!(rewound
+            .position
+            .approx_eq(&Vector3D::from_coords(1.0, 0.0, 0.0), 1e-6));
+
+        let back = camera.rewound(&motion, 1.0);
+        assert!(back
+            .look_direction()
+            .approx_eq(&previous.look_direction(), 1e-5));
+    }
+
+    #[test]
+    fn orbit_starts_at_the_given_distance() {
+        let mut camera = Camera::new();
+        let target = Vector3D::from_coords(0.0, 0.0, 4.0);
+        camera.set_orbit_target(target, 10.0);
+        assert!(camera
+            .position
+            .approx_eq(&Vector3D::from_coords(0.0, 0.0, -6.0), 1e-5));
+        assert!(camera
+            .look_direction()
+            .approx_eq(&Vector3D::from_coords(0.0, 0.0, 1.0), 1e-5));
+
+        // Toggling keeps the distance the camera is at
+        camera.toggle_mode();
+        camera.toggle_mode();
+        assert_eq!(camera.mode, CameraMode::Orbit);
+        assert!((camera.distance - 10.0).abs() < 1e-4);
+    }
+
+    proptest! {
+        #[test]
+        fn orbit_keeps_distance_to_target(yaw in -10.0f32..10.0, pitch in -3.0f32..3.0) {
+            let mut camera = Camera::new();
+            camera.set_orbit_target(Vector3D::from_coords(0.0, 1.0, 4.0), 3.0);
+            let distance = camera.distance;
+            camera.rotate(yaw, pitch);
+
+            let to_target = camera.target - camera.position;
+            prop_assert!((vector_len(&to_target) - distance).abs() < 1e-3);
+            prop_assert!(camera.look_direction().approx_eq(&to_target.from_normalise(), 1e-4));
+        }
+    }
+}
@@ -1,40 +1,78 @@
 extern crate sdl2;
 
-use std::{collections::VecDeque, ffi::NulError};
+use std::{
+    ffi::NulError,
+    path::Path,
+    thread::sleep,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use sdl2::{
-    keyboard::Keycode,
+    event::{Event, WindowEvent},
     pixels::{Color, PixelFormatEnum},
-    render::Canvas,
+    rect::Rect,
+    render::{Canvas, Texture},
     video::Window,
     Sdl,
 };
 
 use super::{
-    math::{interpolate, vector_cross_product, vector_dot_product},
-    matrix_4x4::Matrix4X4,
+    application::Application,
+    camera::{Camera, CameraMotion},
+    frame_buffer::FrameBuffer,
+    hud::Hud,
+    input::{Action, InputMap},
     mesh::Mesh,
-    triangle::Triangle,
+    quaternion::Quaternion,
+    recording::{load_recording, InputRecorder, Recording},
+    renderer::{CullMode, FrameStats, PickResult, RenderMode, Renderer},
+    scene_object::SceneObject,
+    timestep::FixedTimestep,
     vector_3d::Vector3D,
 };
 
+// A recording that drives the main loop instead of live input
+struct Replay {
+    recording: Recording,
+    // Marked frames are saved as `<prefix>-<frame>.bmp`
+    screenshot_prefix: String,
+}
+
 pub struct Engine {
     pub sdl_context: Sdl,
     canvas: Canvas<Window>,
-    pixel_buffer: Vec<u8>,
-    z_buffer: Vec<f32>,
-    size_x: u32,
-    size_y: u32,
-    projection_matrix: Matrix4X4,
-    mesh: Mesh,
-    camera: Vector3D,
-    look_direction: Vector3D,
-    r_yaw: f32,
-    u_pitch: f32,
+    // Streaming texture the frame is uploaded to, created on first use
+    texture: Option<Texture>,
+    renderer: Renderer,
+    // Fixed internal resolution scaled up to the window, `None` renders at the window size
+    render_size: Option<(u32, u32)>,
+    objects: Vec<SceneObject>,
+    lod_levels: usize,
+    // Always at least one, the scene is seen through the active one
+    cameras: Vec<Camera>,
+    active_camera: usize,
+    // Frames are rendered but never shown
+    headless: bool,
+    // How the active camera moved in the last update step, and how far the next
+    // frame is from that step to the next one
+    camera_motion: CameraMotion,
+    // How every object turned in the last update step
+    object_motions: Vec<Quaternion>,
+    interpolation: f32,
+    input: InputMap,
+    // Fixed update steps per second
+    tick_rate: f32,
+    // Frame cap, `None` draws frames as fast as possible
+    max_fps: Option<f32>,
+    vsync: bool,
+    record_path: Option<String>,
+    replay: Option<Replay>,
+    hud: Hud,
 }
 
 impl Engine {
-    pub fn new(title: &str, size_x: u32, size_y: u32, object_to_load: &str) -> Engine {
+    // With `vsync` presenting waits for the display, which limits the frame rate
+    pub fn new(title: &str, size_x: u32, size_y: u32, lod_levels: usize, vsync: bool) -> Engine {
         let sdl_context = sdl2::init().expect("Error creating SDL context");
         let video_subsystem = sdl_context.video().expect("Error creating video subsystem");
 
@@ -46,440 +84,604 @@ impl Engine {
             .build()
             .expect("Error creating window");
 
-        let canvas = window
-            .into_canvas()
-            .accelerated()
-            .build()
-            .expect("Error creating canvas");
-
-        let near_plane: f32 = 0.1;
-        let far_plane: f32 = 1000.0;
-        let fov: f32 = 90.0;
-        let aspect_ratio: f32 = size_y as f32 / size_x as f32;
-
-        let projection_matrix =
-            Matrix4X4::from_projection(fov, aspect_ratio, near_plane, far_plane);
-
-        // Create pixel and z buffers
-        let buffer_size = (size_x * size_y) as usize;
-        let pixel_buffer = vec![0u8; buffer_size * 3]; // RGB format: 3 bytes per pixel
-        let z_buffer = vec![f32::INFINITY; buffer_size]; // Initialize with far values
+        let mut canvas_builder = window.into_canvas().accelerated();
+        if vsync {
+            canvas_builder = canvas_builder.present_vsync();
+        }
+        let canvas = canvas_builder.build().expect("Error creating canvas");
 
         Engine {
             sdl_context,
             canvas,
-            pixel_buffer,
-            z_buffer,
-            size_x,
-            size_y,
-            projection_matrix,
-            mesh: Mesh::from_file(object_to_load),
-            camera: Vector3D::new(),
-            look_direction: Vector3D::from_coords(0.0, 0.0, 1.0),
-            r_yaw: 0.0,
-            u_pitch: 0.0,
+            texture: None,
+            renderer: Renderer::new(size_x, size_y),
+            render_size: None,
+            objects: vec![],
+            lod_levels,
+            cameras: vec![Camera::new()],
+            active_camera: 0,
+            headless: false,
+            camera_motion: CameraMotion::default(),
+            object_motions: vec![],
+            interpolation: 1.0,
+            input: InputMap::new(),
+            tick_rate: 60.0,
+            max_fps: Some(60.0),
+            vsync,
+            record_path: None,
+            replay: None,
+            hud: Hud::new(),
         }
     }
 
-    pub fn on_user_update(&mut self) -> bool {
-        // Clear pixel buffer (set to black)
-        self.pixel_buffer.fill(0);
-        // Reset z-buffer
-        self.z_buffer.fill(f32::INFINITY);
-
-        let translation_matrix = Matrix4X4::from_translation(0.0, 0.0, 4.0);
+    // Runs the main loop until the window is closed, the quit action is used or
+    // `app` returns false. Input, timing and presenting frames are handled here,
+    // what happens in between is up to `app`.
+    pub fn run(&mut self, app: &mut impl Application) -> Result<(), String> {
+        let mut event_pump = self.sdl_context.event_pump()?;
+        let mut recorder = match &self.record_path {
+            Some(filename) => Some(InputRecorder::create(filename, self.tick_rate)?),
+            None => None,
+        };
+        // A replay has to use the rate it was recorded with to end up in the same place
+        let tick_rate = match &self.replay {
+            Some(replay) => replay.recording.tick_rate,
+            None => self.tick_rate,
+        };
+        let mut timestep = FixedTimestep::new(tick_rate);
+
+        if !app.on_user_create(self) {
+            return Ok(());
+        }
 
-        let world_matrix = &Matrix4X4::from_identity() * &translation_matrix;
+        let mut running = true;
+        let mut frame = 0;
+        let mut last_frame_time = Instant::now();
+        while running {
+            let now = Instant::now();
+            let mut frame_time = now.duration_since(last_frame_time).as_secs_f32();
+            last_frame_time = now;
+
+            let events: Vec<Event> = match &self.replay {
+                Some(replay) => {
+                    // The window can still be closed, any other input is ignored
+                    let closed = event_pump
+                        .poll_iter()
+                        .any(|event| matches!(event, Event::Quit { .. }));
+                    match replay.recording.frames.get(frame) {
+                        Some(recorded_frame) if !closed => {
+                            frame_time = recorded_frame.delta;
+                            recorded_frame.events.clone()
+                        }
+                        _ => break,
+                    }
+                }
+                None => event_pump.poll_iter().collect(),
+            };
+            if let Some(recorder) = &mut recorder {
+                recorder.record_frame(frame_time, &events)?;
+            }
 
-        let up_vector = Vector3D::from_coords(0.0, 1.0, 0.0);
-        let mut target_vector = Vector3D::from_coords(0.0, 0.0, 1.0);
+            let mut marked = false;
+            for event in &events {
+                let actions = self.input.handle_event(event);
+                match *event {
+                    Event::Quit { .. } => running = false,
+                    Event::Window {
+                        win_event: WindowEvent::Resized(new_x, new_y),
+                        ..
+                    } => self.resize_window(new_x, new_y),
+                    _ => {}
+                }
+                for action in &actions {
+                    match action {
+                        Action::Quit => running = false,
+                        Action::ToggleHud => self.hud.visible = !self.hud.visible,
+                        Action::Screenshot => self.save_screenshot(),
+                        Action::MarkFrame => marked = true,
+                        _ => {}
+                    }
+                }
+                app.on_user_input(self, event, &actions);
+            }
 
-        let camera_rotation_matrix =
-            &Matrix4X4::from_rotation_x(self.u_pitch) * &Matrix4X4::from_rotation_y(self.r_yaw);
+            for _ in 0..timestep.advance(frame_time) {
+                self.fixed_update(app, timestep.step);
+            }
+            self.interpolation = timestep.alpha();
 
-        self.look_direction = &camera_rotation_matrix * &target_vector;
-        target_vector = &self.camera + &self.look_direction;
+            if !app.on_user_update(self, frame_time) {
+                running = false;
+            }
+            self.hud.update(frame_time);
+            if self.hud.visible {
+                self.draw_hud();
+            }
+            self.present();
 
-        let camera_matrix = Matrix4X4::from_point_at(&self.camera, &target_vector, &up_vector);
-        let view_matrix = camera_matrix.quick_inverse();
+            if marked {
+                self.mark_frame(frame);
+            }
+            frame += 1;
+
+            // Frame delay: a replay on screen follows the recorded frame times, otherwise
+            // the frame cap is used. With vsync presenting already waits.
+            let target_frame_duration = match &self.replay {
+                Some(_) if self.headless => None,
+                Some(_) => Some(Duration::from_secs_f32(frame_time.max(0.0))),
+                None if self.vsync => None,
+                None => self.max_fps.map(|max_fps| Duration::from_secs_f32(1.0 / max_fps)),
+            };
+            if let Some(target_frame_duration) = target_frame_duration {
+                let render_time = last_frame_time.elapsed();
+                if render_time < target_frame_duration {
+                    sleep(target_frame_duration - render_time);
+                }
+            }
+        }
 
-        let mut triangles_to_draw: Vec<Triangle> = Vec::new();
+        app.on_user_destroy(self);
+        if let Some(replay) = &self.replay {
+            println!("Replayed {} of {} frames", frame, replay.recording.frames.len());
+        }
 
-        // Do all transformations
-        for triangle in &self.mesh.triangles {
-            let mut transformed_triangle = Triangle::new();
-            let mut viewed_triangle = Triangle::new();
+        Ok(())
+    }
 
-            transformed_triangle.vectors[0] = &world_matrix * &triangle.vectors[0];
-            transformed_triangle.vectors[1] = &world_matrix * &triangle.vectors[1];
-            transformed_triangle.vectors[2] = &world_matrix * &triangle.vectors[2];
+    // Runs one update step of `app`. How the active camera and the objects moved is
+    // remembered, so frames drawn before the next step can show them part of the way
+    // there.
+    fn fixed_update(&mut self, app: &mut impl Application, step: f32) {
+        let active_camera = self.active_camera;
+        let previous_camera = self.camera().clone();
+        let previous_rotations: Vec<Quaternion> =
+            self.objects.iter().map(|object| object.rotation.clone()).collect();
+        app.on_fixed_update(self, step);
+
+        self.camera_motion = if self.active_camera == active_camera {
+            self.camera().motion_since(&previous_camera)
+        } else {
+            CameraMotion::default()
+        };
+        // Objects added during the step have no motion, they are drawn where they are
+        self.object_motions = self
+            .objects
+            .iter()
+            .zip(&previous_rotations)
+            .map(|(object, previous)| (&object.rotation * &previous.conjugate()).normalise())
+            .collect();
+    }
 
-            // Calculate normals
-            let line1 = &transformed_triangle.vectors[1] - &transformed_triangle.vectors[0];
-            let line2 = &transformed_triangle.vectors[2] - &transformed_triangle.vectors[0];
+    // Renders the scene into the frame buffer, 2D drawing can go on top before it's
+    // presented
+    pub fn render_scene(&mut self) {
+        let camera = self.interpolated_camera();
+
+        // Turn the objects back like the camera for drawing, then restore them so
+        // updates and picking see where they are after the last step
+        let amount = 1.0 - self.interpolation;
+        let rotations: Vec<Quaternion> =
+            self.objects.iter().map(|object| object.rotation.clone()).collect();
+        for (object, motion) in self.objects.iter_mut().zip(&self.object_motions) {
+            let previous_rotation = &motion.conjugate() * &object.rotation;
+            object.rotation = object.rotation.slerp(&previous_rotation, amount);
+        }
+        self.renderer.render(&self.objects, &camera);
+        for (object, rotation) in self.objects.iter_mut().zip(rotations) {
+            object.rotation = rotation;
+        }
+    }
 
-            // Get cross product of lines to get normal to triangle surface
-            let normal = vector_cross_product(&line1, &line2).from_normalise();
+    // The camera between where it was and where it is after the last update step
+    fn interpolated_camera(&self) -> Camera {
+        self.camera().rewound(&self.camera_motion, 1.0 - self.interpolation)
+    }
 
-            let camera_ray = &transformed_triangle.vectors[0] - &self.camera;
+    // Draws the HUD over whatever the application drew, `run` does this before
+    // presenting when it's visible
+    fn draw_hud(&mut self) {
+        let stats = self.renderer.stats().clone();
+        let render_mode = format!(
+            "{}, culling {}",
+            self.renderer.render_mode.name(),
+            self.renderer.cull_mode.name()
+        );
+        // The camera the frame was drawn with, not where the next update step ends up
+        let camera = self.interpolated_camera();
+        self.hud.draw(&mut self.renderer.frame_buffer, &stats, &camera, &render_mode);
+    }
 
-            // Temporarily off for debugging purposes
-            if vector_dot_product(&normal, &camera_ray) >= 0.0 {
-                continue;
-            }
+    // For drawing on top of the scene, before the frame is presented
+    pub fn frame_buffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.renderer.frame_buffer
+    }
 
-            // Calculate illumination
-            let light_direction = Vector3D::from_coords(0.0, 0.0, -1.0).from_normalise();
-            let dot_product = f32::max(0.1, vector_dot_product(&light_direction, &normal));
-
-            // Convert world space to view space
-            viewed_triangle.vectors[0] = &view_matrix * &transformed_triangle.vectors[0];
-            viewed_triangle.vectors[1] = &view_matrix * &transformed_triangle.vectors[1];
-            viewed_triangle.vectors[2] = &view_matrix * &transformed_triangle.vectors[2];
-
-            // Clip viewed triangle against the near plane
-            let clipped_triangles = &viewed_triangle.clip_against_plane(
-                Vector3D::from_coords(0.0, 0.0, 0.1),
-                Vector3D::from_coords(0.0, 0.0, 1.0),
-            );
-
-            for clipped_triangle in clipped_triangles {
-                let mut projected_triangle = Triangle::new();
-                projected_triangle.base_color = clipped_triangle.base_color;
-                projected_triangle.base_color =
-                    self.get_color(dot_product, projected_triangle.base_color);
-
-                // Project triangles from 3D to 2D
-                projected_triangle.vectors[0] =
-                    &self.projection_matrix * &clipped_triangle.vectors[0];
-                projected_triangle.vectors[1] =
-                    &self.projection_matrix * &clipped_triangle.vectors[1];
-                projected_triangle.vectors[2] =
-                    &self.projection_matrix * &clipped_triangle.vectors[2];
-
-                projected_triangle.vectors[0] =
-                    &projected_triangle.vectors[0] / projected_triangle.vectors[0].w;
-                projected_triangle.vectors[1] =
-                    &projected_triangle.vectors[1] / projected_triangle.vectors[1].w;
-                projected_triangle.vectors[2] =
-                    &projected_triangle.vectors[2] / projected_triangle.vectors[2].w;
-
-                let offset_view = Vector3D::from_coords(1.0, 1.0, 0.0);
-                projected_triangle.vectors[0] = &projected_triangle.vectors[0] + &offset_view;
-                projected_triangle.vectors[1] = &projected_triangle.vectors[1] + &offset_view;
-                projected_triangle.vectors[2] = &projected_triangle.vectors[2] + &offset_view;
-
-                projected_triangle.vectors[0].x *= 0.5 * self.size_x as f32;
-                projected_triangle.vectors[0].y *= 0.5 * self.size_y as f32;
-                projected_triangle.vectors[1].x *= 0.5 * self.size_x as f32;
-                projected_triangle.vectors[1].y *= 0.5 * self.size_y as f32;
-                projected_triangle.vectors[2].x *= 0.5 * self.size_x as f32;
-                projected_triangle.vectors[2].y *= 0.5 * self.size_y as f32;
-
-                triangles_to_draw.push(projected_triangle);
-            }
+    // Shows the frame buffer in the window, `run` does this after every update
+    pub fn present(&mut self) {
+        if !self.headless {
+            let present_start = Instant::now();
+            self.render_buffer_to_screen();
+            self.renderer.stats_mut().present_time = present_start.elapsed();
         }
+    }
 
-        // First, sort all the triangles
-        triangles_to_draw.sort_by(|t1, t2| {
-            let z1 = (t1.vectors[0].z + t1.vectors[1].z + t1.vectors[2].z) / 3.0;
-            let z2 = (t2.vectors[0].z + t2.vectors[1].z + t2.vectors[2].z) / 3.0;
+    // Casts a ray from the camera through a pixel and returns the closest hit
+    pub fn pick(&self, screen_x: i32, screen_y: i32) -> Option<PickResult> {
+        self.renderer.pick(&self.objects, screen_x, screen_y)
+    }
 
-            z2.partial_cmp(&z1).unwrap_or_else(|| {
-                if z1.is_nan() {
-                    std::cmp::Ordering::Less
-                } else {
-                    std::cmp::Ordering::Greater
-                }
-            })
+    // Picks at a screen position and highlights the result
+    pub fn select(&mut self, screen_x: i32, screen_y: i32) -> Option<&PickResult> {
+        self.renderer.selected = self.pick(screen_x, screen_y);
+        self.renderer.selected.as_ref()
+    }
+
+    fn render_buffer_to_screen(&mut self) {
+        let frame_buffer = &self.renderer.frame_buffer;
+
+        // The texture is kept between frames and only recreated after a resize
+        let texture = self.texture.get_or_insert_with(|| {
+            self.canvas
+                .texture_creator()
+                .create_texture_streaming(
+                    PixelFormatEnum::RGB24,
+                    frame_buffer.width,
+                    frame_buffer.height,
+                )
+                .expect("Failed to create texture")
         });
 
-        // Rasterize everything to the screen
-        // Define clipping planes
-        let clipping_planes = [
-            (
-                Vector3D::from_coords(0.0, 0.0, 0.0),
-                Vector3D::from_coords(0.0, 1.0, 0.0),
-            ), // Top
-            (
-                Vector3D::from_coords(0.0, self.size_y as f32 - 1.0, 0.0),
-                Vector3D::from_coords(0.0, -1.0, 0.0),
-            ), // Bottom
-            (
-                Vector3D::from_coords(0.0, 0.0, 0.0),
-                Vector3D::from_coords(1.0, 0.0, 0.0),
-            ), // Left
-            (
-                Vector3D::from_coords(self.size_x as f32 - 1.0, 0.0, 0.0),
-                Vector3D::from_coords(-1.0, 0.0, 0.0),
-            ), // Right
-        ];
-        for triangle_to_draw in triangles_to_draw {
-            let mut triangle_queue: VecDeque<Triangle> = VecDeque::new();
-            triangle_queue.push_back(triangle_to_draw);
-
-            for (plane_position, plane_normal) in &clipping_planes {
-                let mut next_queue = VecDeque::new();
-
-                while let Some(test_triangle) = triangle_queue.pop_front() {
-                    // Clip triangle against the current plane
-                    let clipped_triangles = test_triangle
-                        .clip_against_plane(plane_position.clone(), plane_normal.clone());
-
-                    // Add clipped triangles to the next queue
-                    for clipped_triangle in clipped_triangles {
-                        next_queue.push_back(clipped_triangle);
+        // Update texture with pixel buffer data
+        texture
+            .with_lock(None, |buffer: &mut [u8], pitch: usize| {
+                let row_size = frame_buffer.width as usize * 3;
+
+                // Without padding at the end of the rows, everything is copied at once
+                if pitch == row_size {
+                    let size = buffer.len().min(frame_buffer.pixels.len());
+                    buffer[..size].copy_from_slice(&frame_buffer.pixels[..size]);
+                    return;
+                }
+
+                for y in 0..frame_buffer.height as usize {
+                    let src_offset = y * row_size;
+                    let dst_offset = y * pitch;
+
+                    if src_offset + row_size <= frame_buffer.pixels.len()
+                        && dst_offset + row_size <= buffer.len()
+                    {
+                        buffer[dst_offset..dst_offset + row_size].copy_from_slice(
+                            &frame_buffer.pixels[src_offset..src_offset + row_size],
+                        );
                     }
                 }
+            })
+            .expect("Failed to update texture");
 
-                // Swap queues for the next iteration
-                triangle_queue = next_queue;
+        // A fixed resolution frame is scaled up and centered, with black bars around it
+        let destination = match self.render_size {
+            Some(_) => {
+                let window_size = self.canvas.output_size().expect("Failed to get window size");
+                Some(letterbox((frame_buffer.width, frame_buffer.height), window_size))
             }
+            None => None,
+        };
+        self.canvas.set_draw_color(Color::BLACK);
+        self.canvas.clear();
+
+        // Render texture to canvas
+        self.canvas
+            .copy(texture, None, destination)
+            .expect("Failed to copy texture to canvas");
 
-            // Draw all remaining triangles in the queue
-            for final_triangle in triangle_queue {
-                self.draw_filled_triangle_to_buffer(&final_triangle);
-                // self.draw_wireframe(&final_triangle);
+        // Present the final result
+        self.canvas.present();
+    }
+
+    // Moves or turns the active camera for movement actions, other actions are ignored
+    pub fn move_camera(&mut self, action: Action, elapsed_time: f32) {
+        let camera = self.camera_mut();
+        let vector_forward = camera.look_direction() * (8.0 * elapsed_time);
+        let vector_up = Vector3D::from_coords(0.0, 8.0 * elapsed_time, 0.0);
+        match action {
+            Action::Ascend => camera.translate(&vector_up),
+            Action::Descend => camera.translate(&-vector_up),
+            Action::TurnLeft => camera.rotate(-8.0 * elapsed_time, 0.0),
+            Action::TurnRight => camera.rotate(8.0 * elapsed_time, 0.0),
+            Action::RollLeft => camera.roll(-2.0 * elapsed_time),
+            Action::RollRight => camera.roll(2.0 * elapsed_time),
+            Action::MoveForward => {
+                camera.translate(&vector_forward);
+            }
+            Action::MoveBackward => {
+                camera.translate(&-vector_forward);
             }
+            Action::StrafeRight => {
+                camera.translate(&(camera.left_direction() * (-8.0 * elapsed_time)));
+            }
+            Action::StrafeLeft => {
+                camera.translate(&(camera.left_direction() * (8.0 * elapsed_time)));
+            }
+            _ => {}
         }
+    }
 
-        // Create texture and render the pixel buffer to screen
-        self.render_buffer_to_screen();
+    pub fn add_object(&mut self, mesh: Mesh, position: Vector3D) {
+        self.objects.push(SceneObject::new(mesh, position, self.lod_levels));
+    }
 
-        true
+    // Turns every object around its own origin, after the rotation it already has
+    pub fn rotate_objects(&mut self, rotation: &Quaternion) {
+        for object in &mut self.objects {
+            object.rotation = (&object.rotation * rotation).normalise();
+        }
     }
 
-    fn get_color(&self, lum: f32, base_color: Color) -> Color {
-        // Clamp luminance between 0.0 and 1.0
-        let luminance = lum.clamp(0.0, 1.0);
+    pub fn stats(&self) -> &FrameStats {
+        self.renderer.stats()
+    }
 
-        // Calculate the new color components
-        let new_r = (base_color.r as f32 * luminance) as u8;
-        let new_g = (base_color.g as f32 * luminance) as u8;
-        let new_b = (base_color.b as f32 * luminance) as u8;
+    pub fn object_count(&self) -> usize {
+        self.objects.len()
+    }
 
-        Color::RGB(new_r, new_g, new_b)
+    pub fn size(&self) -> (u32, u32) {
+        (
+            self.renderer.frame_buffer.width,
+            self.renderer.frame_buffer.height,
+        )
     }
 
-    pub fn draw_filled_triangle_to_buffer(&mut self, projected_triangle: &Triangle) {
-        // Order projected points from top to bottom by their `y` value
-        let mut ordered_points = projected_triangle.vectors.clone();
-        ordered_points.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap());
+    // Returns the subdivision level of the objects afterwards
+    pub fn change_subdivision_level(&mut self, delta: i32) -> usize {
+        // Triangle indices change with the mesh
+        self.renderer.selected = None;
+        let mut new_level = 0;
+        for object in &mut self.objects {
+            let level = (object.subdivision_level() as i32 + delta).max(0) as usize;
+            new_level = object.set_subdivision_level(level);
+        }
+        new_level
+    }
 
-        // Deconstruct the sorted points
-        let p0 = &ordered_points[0];
-        let p1 = &ordered_points[1];
-        let p2 = &ordered_points[2];
+    // Triangles of all objects at their current subdivision level, at full detail
+    pub fn triangle_count(&self) -> usize {
+        let triangle_count = |object: &SceneObject| object.lod_chain().levels[0].triangles.len();
+        self.objects.iter().map(triangle_count).sum()
+    }
 
-        // Interpolate x-coordinates along the edges
-        let x01 = interpolate(p0.x, p0.y, p1.x, p1.y);
-        let x12 = interpolate(p1.x, p1.y, p2.x, p2.y);
-        let x02 = interpolate(p0.x, p0.y, p2.x, p2.y);
+    // Places the active camera at `position`, turned towards `target`
+    pub fn look_at(&mut self, position: Vector3D, target: &Vector3D) {
+        let camera = self.camera_mut();
+        camera.position = position;
+        camera.look_at(target);
+    }
 
-        // Merge x01 and x12 for the full edge from p0 to p2
-        let mut x_full = x01;
-        x_full.pop(); // Avoid duplicating the middle point
-        x_full.extend(x12);
+    pub fn rotate_camera(&mut self, rel_x: f32, rel_y: f32) {
+        self.camera_mut().rotate(rel_x * 0.01, rel_y * 0.01);
+    }
 
-        // Determine left and right edges based on midpoint comparison
-        let x_left;
-        let x_right;
-        let mid_index = x02.len() / 2;
+    pub fn pan_camera(&mut self, rel_x: f32, rel_y: f32) {
+        self.camera_mut().pan(rel_x, rel_y);
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.cameras[self.active_camera]
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.cameras[self.active_camera]
+    }
+
+    // Adds a camera to switch to, returns its index
+    pub fn add_camera(&mut self, camera: Camera) -> usize {
+        self.cameras.push(camera);
+        self.cameras.len() - 1
+    }
+
+    pub fn set_active_camera(&mut self, index: usize) {
+        if index < self.cameras.len() {
+            self.active_camera = index;
+            self.camera_motion = CameraMotion::default();
+        }
+    }
+
+    // Switches to the next camera, after the last one comes the first again
+    pub fn next_camera(&mut self) -> usize {
+        self.active_camera = (self.active_camera + 1) % self.cameras.len();
+        self.camera_motion = CameraMotion::default();
+        self.active_camera
+    }
+
+    pub fn resize_window(&mut self, new_x: i32, new_y: i32) {
+        // Nothing to draw into while the window is minimized
+        if new_x <= 0 || new_y <= 0 {
+            return;
+        }
+
+        // A fixed resolution is only scaled differently
+        if self.render_size.is_none() {
+            // Resize buffers to match new window size
+            self.renderer.resize(new_x as u32, new_y as u32);
+            self.destroy_texture();
+        }
+    }
+
+    // Renders at a fixed resolution that is scaled to fit the window, or at the
+    // window size again with `None`
+    pub fn set_render_size(&mut self, render_size: Option<(u32, u32)>) {
+        self.render_size = render_size;
+        let (size_x, size_y) = match render_size {
+            Some(size) => size,
+            None => self.canvas.output_size().expect("Failed to get window size"),
+        };
+        self.renderer.resize(size_x, size_y);
+        self.destroy_texture();
+    }
 
-        if x02[mid_index] < x_full[mid_index] {
-            x_left = x02;
-            x_right = x_full;
+    // Hides the window and stops presenting frames, they can still be saved with `screenshot`
+    pub fn set_headless(&mut self, headless: bool) {
+        self.headless = headless;
+        if headless {
+            self.canvas.window_mut().hide();
         } else {
-            x_left = x_full;
-            x_right = x02;
+            self.canvas.window_mut().show();
         }
+    }
 
-        // Get color components
-        let color = projected_triangle.base_color;
-
-        // Ensure we stay within bounds of the interpolation arrays
-        let start_y = p0.y as i32;
-        let end_y = p2.y as i32;
-
-        for y in start_y..end_y {
-            let index = (y - start_y) as usize;
-
-            if index < x_left.len() && index < x_right.len() {
-                let x_start = x_left[index] as i32;
-                let x_end = x_right[index] as i32;
-
-                for x in x_start..x_end {
-                    let screen_x = (self.size_x as i32 - x) as usize;
-                    let screen_y = (self.size_y as i32 - y) as usize;
-
-                    // Bounds checking
-                    if screen_x < self.size_x as usize && screen_y < self.size_y as usize {
-                        let pixel_index = (screen_y * self.size_x as usize + screen_x) * 3;
-                        
-                        // Write RGB values directly to buffer
-                        if pixel_index + 2 < self.pixel_buffer.len() {
-                            self.pixel_buffer[pixel_index] = color.r;     // Red
-                            self.pixel_buffer[pixel_index + 1] = color.g; // Green
-                            self.pixel_buffer[pixel_index + 2] = color.b; // Blue
-                        }
-                    }
-                }
-            }
+    // The texture is created again with the new size on the next frame
+    fn destroy_texture(&mut self) {
+        if let Some(texture) = self.texture.take() {
+            // SAFETY: the canvas that created the texture is still alive
+            unsafe { texture.destroy() };
         }
     }
 
-    fn render_buffer_to_screen(&mut self) {
-        // Create texture creator
-        let texture_creator = self.canvas.texture_creator();
-        
-        // Create a streaming texture
-        let mut texture = texture_creator
-            .create_texture_streaming(PixelFormatEnum::RGB24, self.size_x, self.size_y)
-            .expect("Failed to create texture");
+    pub fn render_mode(&self) -> RenderMode {
+        self.renderer.render_mode
+    }
 
-        // Update texture with pixel buffer data
-        texture
-            .with_lock(None, |buffer: &mut [u8], pitch: usize| {
-                for y in 0..self.size_y as usize {
-                    let src_offset = y * self.size_x as usize * 3;
-                    let dst_offset = y * pitch;
-                    let row_size = self.size_x as usize * 3;
-                    
-                    if src_offset + row_size <= self.pixel_buffer.len() 
-                        && dst_offset + row_size <= buffer.len() {
-                        buffer[dst_offset..dst_offset + row_size]
-                            .copy_from_slice(&self.pixel_buffer[src_offset..src_offset + row_size]);
-                    }
-                }
-            })
-            .expect("Failed to update texture");
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.renderer.render_mode = render_mode;
+    }
 
-        // Render texture to canvas
-        self.canvas
-            .copy(&texture, None, None)
-            .expect("Failed to copy texture to canvas");
+    // Switches to the next render mode and returns it
+    pub fn next_render_mode(&mut self) -> RenderMode {
+        self.renderer.render_mode = self.renderer.render_mode.next();
+        self.renderer.render_mode
+    }
 
-        // Present the final result
-        self.canvas.present();
+    pub fn cull_mode(&self) -> CullMode {
+        self.renderer.cull_mode
     }
 
-    pub fn draw_wireframe(&mut self, triangle: &Triangle) {
-        let color = Color::RGB(255, 0, 0); // Red wireframe
-        
-        // Draw the three edges of the triangle
-        self.draw_line_to_buffer(
-            triangle.vectors[0].x, triangle.vectors[0].y,
-            triangle.vectors[1].x, triangle.vectors[1].y,
-            color
-        );
-        self.draw_line_to_buffer(
-            triangle.vectors[1].x, triangle.vectors[1].y,
-            triangle.vectors[2].x, triangle.vectors[2].y,
-            color
-        );
-        self.draw_line_to_buffer(
-            triangle.vectors[2].x, triangle.vectors[2].y,
-            triangle.vectors[0].x, triangle.vectors[0].y,
-            color
-        );
+    pub fn set_cull_mode(&mut self, cull_mode: CullMode) {
+        self.renderer.cull_mode = cull_mode;
     }
 
-    fn draw_line_to_buffer(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, color: Color) {
-        // Bresenham's line algorithm for drawing lines to pixel buffer
-        let mut x0 = (self.size_x as f32 - x0) as i32;
-        let mut y0 = (self.size_y as f32 - y0) as i32;
-        let x1 = (self.size_x as f32 - x1) as i32;
-        let y1 = (self.size_y as f32 - y1) as i32;
-
-        let dx = (x1 - x0).abs();
-        let dy = -(y1 - y0).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx + dy;
-
-        loop {
-            // Draw pixel at (x0, y0)
-            if x0 >= 0 && x0 < self.size_x as i32 && y0 >= 0 && y0 < self.size_y as i32 {
-                let pixel_index = (y0 as usize * self.size_x as usize + x0 as usize) * 3;
-                if pixel_index + 2 < self.pixel_buffer.len() {
-                    self.pixel_buffer[pixel_index] = color.r;
-                    self.pixel_buffer[pixel_index + 1] = color.g;
-                    self.pixel_buffer[pixel_index + 2] = color.b;
-                }
-            }
+    // Switches between culling nothing, back faces and front faces, returns the new mode
+    pub fn next_cull_mode(&mut self) -> CullMode {
+        self.renderer.cull_mode = self.renderer.cull_mode.next();
+        self.renderer.cull_mode
+    }
 
-            if x0 == x1 && y0 == y1 { break; }
+    // Saves the last rendered frame as a BMP image
+    pub fn screenshot(&self, filename: &str) -> Result<(), String> {
+        self.renderer.frame_buffer.save_bmp(filename)
+    }
 
-            let e2 = 2 * err;
-            if e2 >= dy {
-                err += dy;
-                x0 += sx;
-            }
-            if e2 <= dx {
-                err += dx;
-                y0 += sy;
-            }
+    // Saves the frame in the working directory
+    fn save_screenshot(&self) {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis());
+        let filename = format!("screenshot-{}.bmp", millis);
+        match self.screenshot(&filename) {
+            Ok(()) => println!("Saved screenshot to {}", filename),
+            Err(err) => eprintln!("Failed to save {}: {}", filename, err),
         }
     }
 
-    pub fn move_camera(&mut self, key: Keycode, elapsed_time: f32) {
-        let vector_forward = &self.look_direction * (8.0 * elapsed_time);
-        match key {
-            Keycode::UP => self.camera.y += 8.0 * elapsed_time,
-            Keycode::DOWN => self.camera.y -= 8.0 * elapsed_time,
-            Keycode::LEFT => self.r_yaw -= 8.0 * elapsed_time,
-            Keycode::RIGHT => self.r_yaw += 8.0 * elapsed_time,
-            Keycode::W => {
-                self.camera = &self.camera + &vector_forward;
-            }
-            Keycode::S => {
-                self.camera = &self.camera - &vector_forward;
+    // Marked frames are only numbered while recording, and saved when replaying
+    fn mark_frame(&self, frame: usize) {
+        match &self.replay {
+            Some(replay) => {
+                let filename = format!("{}-{}.bmp", replay.screenshot_prefix, frame);
+                match self.screenshot(&filename) {
+                    Ok(()) => println!("Saved frame {} to {}", frame, filename),
+                    Err(err) => eprintln!("Failed to save {}: {}", filename, err),
+                }
             }
-            Keycode::D => {
-                let target_vector = Vector3D::from_coords(1.0, 0.0, 0.0);
-                let camera_rotation_matrix = Matrix4X4::from_rotation_y(self.r_yaw);
+            None => println!("Marked frame {}", frame),
+        }
+    }
 
-                let right_from_look_direction_direction = &camera_rotation_matrix * &target_vector;
+    pub fn input(&self) -> &InputMap {
+        &self.input
+    }
 
-                self.camera =
-                    &self.camera - &(&right_from_look_direction_direction * (8.0 * elapsed_time));
-            }
-            Keycode::A => {
-                let target_vector = Vector3D::from_coords(-1.0, 0.0, 0.0);
-                let camera_rotation_matrix = Matrix4X4::from_rotation_y(self.r_yaw);
+    pub fn set_input_map(&mut self, input: InputMap) {
+        self.input = input;
+    }
 
-                let left_from_look_direction_direction = &camera_rotation_matrix * &target_vector;
+    // Whether the HUD is shown and the font it uses
+    pub fn hud_mut(&mut self) -> &mut Hud {
+        &mut self.hud
+    }
 
-                self.camera =
-                    &self.camera - &(&left_from_look_direction_direction * (8.0 * elapsed_time));
-            }
-            _ => {}
-        }
+    // How many fixed update steps run per second, see `Application::on_fixed_update`
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.tick_rate = tick_rate;
     }
 
-    pub fn rotate_camera(&mut self, rel_x: f32, rel_y: f32) {
-        self.r_yaw += rel_x * 0.01;
-        self.u_pitch += rel_y * 0.01;
-        let max_pitch = (std::f32::consts::TAU / 4.0) - 0.01;
-
-        if self.u_pitch > max_pitch {
-            self.u_pitch = max_pitch
-        } else if self.u_pitch < -max_pitch {
-            self.u_pitch = -max_pitch
-        }
+    // Limits the frame rate when vsync is off, `None` draws as fast as possible
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.max_fps = max_fps;
     }
 
-    pub fn resize_window(&mut self, new_x: i32, new_y: i32) {
-        self.size_x = new_x as u32;
-        self.size_y = new_y as u32;
-        
-        // Resize buffers to match new window size
-        let buffer_size = (self.size_x * self.size_y) as usize;
-        self.pixel_buffer.resize(buffer_size * 3, 0);
-        self.z_buffer.resize(buffer_size, f32::INFINITY);
+    // Records all input and frame times to a file while running
+    pub fn record(&mut self, filename: &str) {
+        self.record_path = Some(filename.to_string());
+    }
+
+    // Runs a recording made with `record` instead of live input. Frames marked in
+    // it are saved next to the recording.
+    pub fn replay(&mut self, filename: &str) -> Result<(), String> {
+        self.replay = Some(Replay {
+            recording: load_recording(filename)?,
+            screenshot_prefix: Path::new(filename).with_extension("").display().to_string(),
+        });
+        Ok(())
     }
 
     pub fn set_title(&mut self, new_title: String) -> Result<(), NulError> {
         self.canvas.window_mut().set_title(&new_title)
     }
 }
+
+// Where a frame is drawn in the window: scaled up by the largest whole number
+// that fits and centered. A window smaller than the frame scales it down
+// instead, keeping the aspect ratio.
+fn letterbox(frame_size: (u32, u32), window_size: (u32, u32)) -> Rect {
+    let (frame_x, frame_y) = frame_size;
+    let (window_x, window_y) = window_size;
+
+    let integer_scale = (window_x / frame_x).min(window_y / frame_y);
+    let (width, height) = if integer_scale >= 1 {
+        (frame_x * integer_scale, frame_y * integer_scale)
+    } else {
+        let scale = f32::min(
+            window_x as f32 / frame_x as f32,
+            window_y as f32 / frame_y as f32,
+        );
+        (
+            ((frame_x as f32 * scale) as u32).max(1),
+            ((frame_y as f32 * scale) as u32).max(1),
+        )
+    };
+
+    Rect::new(
+        (window_x.saturating_sub(width) / 2) as i32,
+        (window_y.saturating_sub(height) / 2) as i32,
+        width,
+        height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_integer_scale() {
+        // Only a whole 3x fits, what is left over is split between the borders
+        let rect = letterbox((320, 180), (1000, 680));
+        assert_eq!(rect, Rect::new(20, 70, 960, 540));
+    }
+
+    #[test]
+    fn letterbox_smaller_window() {
+        let rect = letterbox((640, 480), (320, 400));
+        assert_eq!(rect, Rect::new(0, 80, 320, 240));
+    }
+}
@@ -7,13 +7,39 @@ use crate::core::triangle::Triangle;
 
 use super::vector_3d::Vector3D;
 
-pub fn load_from_obj_file(filename: &str, output: &mut Vec<Triangle>) -> bool {
-    let file = File::open(filename).expect("Error reading obj file");
-    let buf_reader = BufReader::new(file);
+// Loads the triangles of an obj file. Faces with more than 3 vertices are split
+// into a triangle fan, and are also kept as polygons in `polygons` so
+// algorithms that work on quads (like Catmull-Clark) can use them.
+pub fn load_from_obj_file(
+    filename: &str,
+    output: &mut Vec<Triangle>,
+    polygons: &mut Vec<Vec<Vector3D>>,
+) -> Result<(), String> {
+    let file =
+        File::open(filename).map_err(|err| format!("Error reading {}: {}", filename, err))?;
+
+    match parse_obj(BufReader::new(file), output, polygons) {
+        Some(vertex_count) => {
+            println!("Loaded {} vertices", vertex_count);
+            Ok(())
+        }
+        None => Err(format!("Error reading {}", filename)),
+    }
+}
+
+// Parses obj data from any reader, see `load_from_obj_file`. Returns the number
+// of vertices, or `None` when reading failed.
+pub fn parse_obj(
+    reader: impl BufRead,
+    output: &mut Vec<Triangle>,
+    polygons: &mut Vec<Vec<Vector3D>>,
+) -> Option<usize> {
     let mut vertices: Vec<Vector3D> = vec![];
     let mut triangles: Vec<Triangle> = vec![];
+    let mut faces: Vec<Vec<Vector3D>> = vec![];
+    let mut has_polygons = false;
 
-    for line in buf_reader.lines() {
+    for line in reader.lines() {
         match line {
             Ok(line) => {
                 if line.starts_with("v ") {
@@ -23,36 +49,43 @@ pub fn load_from_obj_file(filename: &str, output: &mut Vec<Triangle>) -> bool {
                         Err(err) => eprintln!("Failed to parse vertex: {}: {}", vertex_data, err),
                     }
                 } else if line.starts_with("f ") {
-                    // Parse face (triangle)
-                    let triangle_data = line.trim_start_matches("f ").trim();
-                    let indices: Vec<&str> = triangle_data.split_whitespace().collect();
-                    if indices.len() == 3 {
-                        let v1 = parse_index(indices[0]);
-                        let v2 = parse_index(indices[1]);
-                        let v3 = parse_index(indices[2]);
+                    // Parse face (triangle or polygon)
+                    let face_data = line.trim_start_matches("f ").trim();
+                    let indices: Option<Vec<usize>> = face_data
+                        .split_whitespace()
+                        .map(|index| parse_index(index).filter(|i| *i < vertices.len()))
+                        .collect();
 
-                        match (v1, v2, v3) {
-                            (Some(v1), Some(v2), Some(v3)) => {
-                                triangles.push(Triangle::from_vectors(&vertices, [v1, v2, v3]));
+                    match indices {
+                        Some(indices) if indices.len() >= 3 => {
+                            for i in 1..indices.len() - 1 {
+                                triangles.push(Triangle::from_vectors(
+                                    &vertices,
+                                    [indices[0], indices[i], indices[i + 1]],
+                                ));
                             }
-                            _ => eprintln!("Invalid face data: {}", triangle_data),
+                            has_polygons |= indices.len() > 3;
+                            faces.push(indices.iter().map(|i| vertices[*i]).collect());
                         }
-                    } else {
-                        eprintln!("Face does not have 3 vertices: {}", triangle_data);
+                        Some(_) => eprintln!("Face has less than 3 vertices: {}", face_data),
+                        None => eprintln!("Invalid face data: {}", face_data),
                     }
                 }
             }
             Err(e) => {
                 eprintln!("Error reading line: {}", e);
-                return false;
+                return None;
             }
         }
     }
 
-    println!("Loaded {} vertices", vertices.len());
     output.clear();
     output.append(&mut triangles);
-    true
+    polygons.clear();
+    if has_polygons {
+        polygons.append(&mut faces);
+    }
+    Some(vertices.len())
 }
 
 fn parse_index(s: &str) -> Option<usize> {
@@ -0,0 +1,442 @@
+use std::{collections::HashMap, fs};
+
+// A fixed size bitmap font. Every glyph fills a cell of the same size, pixels
+// that are set are drawn in the text color and the rest is left alone.
+#[derive(Clone)]
+pub struct Font {
+    pub glyph_width: u32,
+    pub glyph_height: u32,
+    // Row by row, starting at the top left of the cell
+    glyphs: HashMap<char, Vec<bool>>,
+}
+
+impl Font {
+    // An 8x8 font with the printable ASCII characters
+    pub fn built_in() -> Font {
+        let mut font = Font::new(8, 8);
+        for (character, rows) in (' '..='~').zip(BUILT_IN_GLYPHS) {
+            // Bit 0 is the leftmost pixel
+            let pixels = rows
+                .iter()
+                .flat_map(|row| (0..8).map(move |x| row & (1 << x) != 0))
+                .collect();
+            font.glyphs.insert(character, pixels);
+        }
+        font
+    }
+
+    fn new(glyph_width: u32, glyph_height: u32) -> Font {
+        Font {
+            glyph_width,
+            glyph_height,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    // Loads a PSF (version 1 or 2) or BDF font, whichever the file contains
+    pub fn from_file(filename: &str) -> Result<Font, String> {
+        let bytes =
+            fs::read(filename).map_err(|err| format!("Error reading {}: {}", filename, err))?;
+        let font = if bytes.starts_with(&PSF1_MAGIC) || bytes.starts_with(&PSF2_MAGIC) {
+            Font::from_psf(&bytes)
+        } else {
+            Font::from_bdf(&String::from_utf8_lossy(&bytes))
+        };
+        font.map_err(|err| format!("{}: {}", filename, err))
+    }
+
+    // The console font format of Linux. Fonts without a unicode table are taken
+    // to have their glyphs in character order.
+    pub fn from_psf(bytes: &[u8]) -> Result<Font, String> {
+        let read_u32 = |offset: usize| -> Result<usize, String> {
+            bytes
+                .get(offset..offset + 4)
+                .map(|value| u32::from_le_bytes([value[0], value[1], value[2], value[3]]) as usize)
+                .ok_or_else(|| "file is too short".to_string())
+        };
+
+        let (width, height, glyph_count, glyph_size, glyphs_start, has_table);
+        if bytes.starts_with(&PSF1_MAGIC) {
+            let mode = *bytes.get(2).ok_or("file is too short")?;
+            height = *bytes.get(3).ok_or("file is too short")? as usize;
+            width = 8;
+            glyph_count = if mode & 0x01 != 0 { 512 } else { 256 };
+            glyph_size = height;
+            glyphs_start = 4;
+            has_table = mode & 0x06 != 0;
+        } else if bytes.starts_with(&PSF2_MAGIC) {
+            glyphs_start = read_u32(8)?;
+            has_table = read_u32(12)? & 0x01 != 0;
+            glyph_count = read_u32(16)?;
+            glyph_size = read_u32(20)?;
+            height = read_u32(24)?;
+            width = read_u32(28)?;
+        } else {
+            return Err("not a PSF font".to_string());
+        }
+
+        let row_size = width.div_ceil(8);
+        if width == 0 || height == 0 || glyph_size < row_size * height {
+            return Err(format!("invalid glyph size {}x{}", width, height));
+        }
+        let table_start = glyphs_start + glyph_count * glyph_size;
+        if bytes.len() < table_start {
+            return Err("file is too short".to_string());
+        }
+
+        // The characters of every glyph
+        let characters: Vec<Vec<char>> = if has_table {
+            let table = &bytes[table_start..];
+            if bytes.starts_with(&PSF1_MAGIC) {
+                psf1_table(table, glyph_count)
+            } else {
+                psf2_table(table, glyph_count)
+            }
+        } else {
+            (0..glyph_count as u32)
+                .map(|index| char::from_u32(index).into_iter().collect())
+                .collect()
+        };
+
+        let mut font = Font::new(width as u32, height as u32);
+        for (index, glyph_characters) in characters.iter().enumerate() {
+            let glyph = &bytes[glyphs_start + index * glyph_size..];
+            let pixels: Vec<bool> = (0..height)
+                .flat_map(|y| {
+                    let row = &glyph[y * row_size..(y + 1) * row_size];
+                    // The leftmost pixel is the highest bit
+                    (0..width).map(move |x| row[x / 8] & (0x80 >> (x % 8)) != 0)
+                })
+                .collect();
+            for character in glyph_characters {
+                font.glyphs.insert(*character, pixels.clone());
+            }
+        }
+
+        Ok(font)
+    }
+
+    // The X11 text format. Glyphs are placed in the font bounding box, so glyphs
+    // of different sizes end up on the same baseline.
+    pub fn from_bdf(text: &str) -> Result<Font, String> {
+        let mut font: Option<Font> = None;
+        // Size and offset of the font bounding box
+        let mut font_box = (0, 0, 0, 0);
+        let mut encoding: Option<char> = None;
+        let mut glyph_box = (0, 0, 0, 0);
+        let mut bitmap: Option<Vec<&str>> = None;
+
+        for (line_number, line) in text.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {}", line_number + 1, message);
+            let mut fields = line.split_whitespace();
+            let keyword = fields.next().unwrap_or("");
+            let mut numbers = || -> Result<(i32, i32, i32, i32), String> {
+                let values = fields
+                    .by_ref()
+                    .map(|field| field.parse::<i32>())
+                    .collect::<Result<Vec<i32>, _>>()
+                    .map_err(|_| error("invalid number"))?;
+                match values[..] {
+                    [width, height, x, y] => Ok((width, height, x, y)),
+                    _ => Err(error("expected width, height and offsets")),
+                }
+            };
+
+            if let Some(rows) = &mut bitmap {
+                if keyword != "ENDCHAR" {
+                    rows.push(keyword);
+                    continue;
+                }
+            }
+
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    font_box = numbers()?;
+                    if font_box.0 <= 0 || font_box.1 <= 0 {
+                        return Err(error("invalid bounding box"));
+                    }
+                    font = Some(Font::new(font_box.0 as u32, font_box.1 as u32));
+                }
+                "STARTCHAR" => encoding = None,
+                // Glyphs that aren't in unicode have a negative encoding
+                "ENCODING" => {
+                    encoding = fields
+                        .next()
+                        .and_then(|value| value.parse::<u32>().ok())
+                        .and_then(char::from_u32)
+                }
+                "BBX" => glyph_box = numbers()?,
+                "BITMAP" => bitmap = Some(vec![]),
+                "ENDCHAR" => {
+                    let font = font
+                        .as_mut()
+                        .ok_or_else(|| error("glyph before FONTBOUNDINGBOX"))?;
+                    let rows = bitmap.take().ok_or_else(|| error("glyph without BITMAP"))?;
+                    if let Some(character) = encoding {
+                        let pixels = place_bdf_glyph(font_box, glyph_box, &rows)
+                            .map_err(|err| error(&err))?;
+                        font.glyphs.insert(character, pixels);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        font.ok_or_else(|| "no FONTBOUNDINGBOX".to_string())
+    }
+
+    pub fn glyph(&self, character: char) -> Option<&[bool]> {
+        self.glyphs.get(&character).map(|pixels| pixels.as_slice())
+    }
+
+    // Width and height in pixels of text, which can have multiple lines
+    pub fn text_size(&self, text: &str) -> (u32, u32) {
+        let columns = text
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let rows = text.lines().count();
+        (
+            columns as u32 * self.glyph_width,
+            rows as u32 * self.glyph_height,
+        )
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Self::built_in()
+    }
+}
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+// Every glyph has a list of UTF-16 characters ending in 0xFFFF. Sequences of
+// combining characters come after 0xFFFE and are skipped.
+fn psf1_table(table: &[u8], glyph_count: usize) -> Vec<Vec<char>> {
+    let mut values = table
+        .chunks_exact(2)
+        .map(|value| u16::from_le_bytes([value[0], value[1]]));
+
+    (0..glyph_count)
+        .map(|_| {
+            values
+                .by_ref()
+                .take_while(|value| *value != 0xFFFF)
+                .collect::<Vec<u16>>()
+                .split(|value| *value == 0xFFFE)
+                .next()
+                .unwrap_or(&[])
+                .iter()
+                .filter_map(|value| char::from_u32(*value as u32))
+                .collect()
+        })
+        .collect()
+}
+
+// Like the PSF1 table, but with UTF-8 characters ending in 0xFF and sequences
+// starting with 0xFE
+fn psf2_table(table: &[u8], glyph_count: usize) -> Vec<Vec<char>> {
+    let mut entries = table.split(|byte| *byte == 0xFF);
+
+    (0..glyph_count)
+        .map(|_| {
+            let entry = entries.next().unwrap_or(&[]);
+            let characters = entry.split(|byte| *byte == 0xFE).next().unwrap_or(&[]);
+            String::from_utf8_lossy(characters)
+                .chars()
+                .filter(|character| *character != char::REPLACEMENT_CHARACTER)
+                .collect()
+        })
+        .collect()
+}
+
+// Copies the rows of a BDF glyph into a cell of the font bounding box. Rows are
+// hexadecimal, with the leftmost pixel in the highest bit.
+fn place_bdf_glyph(
+    font_box: (i32, i32, i32, i32),
+    glyph_box: (i32, i32, i32, i32),
+    rows: &[&str],
+) -> Result<Vec<bool>, String> {
+    let (font_width, font_height, font_x, font_y) = font_box;
+    let (glyph_width, glyph_height, glyph_x, glyph_y) = glyph_box;
+    // Offsets are from the origin on the baseline, with y going up
+    let left = glyph_x - font_x;
+    let top = (font_height + font_y) - (glyph_height + glyph_y);
+
+    let mut pixels = vec![false; (font_width * font_height) as usize];
+    for (row_index, row) in rows.iter().enumerate().take(glyph_height.max(0) as usize) {
+        let bits =
+            u64::from_str_radix(row, 16).map_err(|_| format!("invalid bitmap row {}", row))?;
+        let bit_count = row.len() as i32 * 4;
+        for x in 0..glyph_width.min(bit_count) {
+            let (cell_x, cell_y) = (left + x, top + row_index as i32);
+            let set = bits & (1 << (bit_count - 1 - x)) != 0;
+            if set && (0..font_width).contains(&cell_x) && (0..font_height).contains(&cell_y) {
+                pixels[(cell_y * font_width + cell_x) as usize] = true;
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+// The printable ASCII characters from space to tilde, 8 rows each. Based on the
+// public domain font8x8 by Daniel Hepper.
+const BUILT_IN_GLYPHS: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // !
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // #
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // $
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // %
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // &
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // (
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // )
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // *
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ,
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // .
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // /
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // 0
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // 1
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // 2
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // 3
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // 4
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // 5
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // 6
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // 7
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // 8
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // 9
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // :
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ;
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // <
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // =
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // >
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // ?
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // @
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // A
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // B
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // C
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // D
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // E
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // F
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // G
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // H
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // I
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // J
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // K
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // L
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // M
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // N
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // O
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // P
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // Q
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // R
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // S
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // T
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // U
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // V
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // W
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // X
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // Y
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // Z
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // [
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // \
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ]
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // _
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // a
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // b
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // c
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // d
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // e
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // f
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // g
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // h
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // i
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // j
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // k
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // l
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // m
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // n
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // o
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // p
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // q
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // r
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // s
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // t
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // u
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // v
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // w
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // x
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // y
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // z
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // {
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // |
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // }
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ~
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_font_has_printable_ascii() {
+        let font = Font::built_in();
+        for character in ' '..='~' {
+            assert_eq!(font.glyph(character).map(|pixels| pixels.len()), Some(64));
+        }
+        assert!(font.glyph(' ').unwrap().iter().all(|pixel| !pixel));
+        assert_eq!(font.text_size("FPS\n60.0"), (32, 16));
+    }
+
+    #[test]
+    fn bdf_glyphs_sit_on_the_baseline() {
+        let bdf = "STARTFONT 2.1\nFONTBOUNDINGBOX 3 4 0 -1\nCHARS 1\n\
+            STARTCHAR i\nENCODING 105\nBBX 1 2 1 0\nBITMAP\n80\n80\nENDCHAR\nENDFONT\n";
+        let font = Font::from_bdf(bdf).unwrap();
+
+        // One row below the baseline is left empty for descenders
+        let expected = [
+            false, false, false, //
+            false, true, false, //
+            false, true, false, //
+            false, false, false,
+        ];
+        assert_eq!(font.glyph('i'), Some(&expected[..]));
+        assert!(Font::from_bdf("STARTCHAR a\nENDCHAR").is_err());
+    }
+
+    #[test]
+    fn psf2_glyphs_follow_the_unicode_table() {
+        let mut bytes = PSF2_MAGIC.to_vec();
+        // Version, header size, flags, glyph count, glyph size, height and width
+        for value in [0u32, 32, 1, 2, 2, 2, 3] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([0b1010_0000, 0b0100_0000, 0b1110_0000, 0]);
+        // Glyph 0 is 'a', glyph 1 is 'é' and the sequence after it is ignored
+        bytes.extend("a".bytes().chain([0xFF]));
+        bytes.extend("é".bytes().chain([0xFE]).chain("e".bytes()).chain([0xFF]));
+        let font = Font::from_psf(&bytes).unwrap();
+
+        assert_eq!((font.glyph_width, font.glyph_height), (3, 2));
+        assert_eq!(
+            font.glyph('a'),
+            Some(&[true, false, true, false, true, false][..])
+        );
+        assert_eq!(
+            font.glyph('é'),
+            Some(&[true, true, true, false, false, false][..])
+        );
+        assert_eq!(font.glyph('e'), None);
+        assert!(Font::from_psf(&bytes[..34]).is_err());
+    }
+}
@@ -0,0 +1,590 @@
+use sdl2::{
+    pixels::{Color, PixelFormatEnum},
+    surface::Surface,
+};
+
+use super::{font::Font, math::interpolate, sprite::Sprite, triangle::Triangle};
+
+// Pixels and depth values of a frame, not tied to a window so frames can also
+// be rendered headless. Drawing triangles of the scene uses projected
+// coordinates, which are flipped on both axes to get screen coordinates. The 2D
+// drawing functions use screen coordinates, with (0, 0) at the top left, and
+// blend colors with an alpha below 255.
+pub struct FrameBuffer {
+    pub width: u32,
+    pub height: u32,
+    // RGB format: 3 bytes per pixel
+    pub pixels: Vec<u8>,
+    pub depth: Vec<f32>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: u32, height: u32) -> FrameBuffer {
+        let buffer_size = (width * height) as usize;
+        FrameBuffer {
+            width,
+            height,
+            pixels: vec![0u8; buffer_size * 3],
+            // Initialize with far values
+            depth: vec![f32::INFINITY; buffer_size],
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+
+        let buffer_size = (width * height) as usize;
+        self.pixels.resize(buffer_size * 3, 0);
+        self.depth.resize(buffer_size, f32::INFINITY);
+    }
+
+    // Sets every pixel to black and every depth to far away
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+        self.depth.fill(f32::INFINITY);
+    }
+
+    pub fn save_bmp(&self, filename: &str) -> Result<(), String> {
+        // SDL wants to be able to write to the pixels, so hand it a copy
+        let mut pixels = self.pixels.clone();
+        let surface = Surface::from_data(
+            &mut pixels,
+            self.width,
+            self.height,
+            self.width * 3,
+            PixelFormatEnum::RGB24,
+        )?;
+        surface.save_bmp(filename)
+    }
+
+    // Fills the pixels of a projected triangle that are not behind what was
+    // drawn before, and stores their depth
+    pub fn draw_filled_triangle(&mut self, projected_triangle: &Triangle) {
+        let color = projected_triangle.base_color;
+        let (pixels, depth) = (&mut self.pixels, &mut self.depth);
+        rasterize(self.width, self.height, projected_triangle, |pixel, z| {
+            // At equal depth the later triangle wins, like without a depth test
+            if z <= depth[pixel] {
+                pixels[pixel * 3..pixel * 3 + 3].copy_from_slice(&[color.r, color.g, color.b]);
+                depth[pixel] = z;
+            }
+        });
+    }
+
+    // Adds one to the count of every pixel a projected triangle covers
+    pub fn count_overdraw(&self, projected_triangle: &Triangle, counts: &mut [u32]) {
+        rasterize(self.width, self.height, projected_triangle, |pixel, _| {
+            counts[pixel] += 1;
+        });
+    }
+
+    // Colors every pixel by how often it was drawn: blue for once, through green and
+    // yellow to red for `max_count` times or more
+    pub fn draw_heatmap(&mut self, counts: &[u32], max_count: u32) {
+        for (pixel, count) in self.pixels.chunks_mut(3).zip(counts) {
+            let color = match *count {
+                0 => Color::BLACK,
+                count => heat_color((count - 1) as f32 / max_count.saturating_sub(1).max(1) as f32),
+            };
+            pixel.copy_from_slice(&[color.r, color.g, color.b]);
+        }
+    }
+
+    // Replaces the pixels with the depth values, from white for the nearest to
+    // black for the furthest. Pixels nothing was drawn on stay black.
+    pub fn draw_depth(&mut self) {
+        let finite_depths = self.depth.iter().filter(|depth| depth.is_finite());
+        let (near, far) = finite_depths
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(near, far), depth| {
+                (near.min(*depth), far.max(*depth))
+            });
+        let range = (far - near).max(f32::EPSILON);
+
+        for (pixel, depth) in self.pixels.chunks_mut(3).zip(&self.depth) {
+            let brightness = if depth.is_finite() {
+                (255.0 * (1.0 - (depth - near) / range)) as u8
+            } else {
+                0
+            };
+            pixel.fill(brightness);
+        }
+    }
+
+    pub fn draw_wireframe(&mut self, triangle: &Triangle, color: Color) {
+        // Draw the three edges of the triangle, flipped to screen coordinates
+        for i in 0..3 {
+            let start = triangle.vectors[i];
+            let end = triangle.vectors[(i + 1) % 3];
+            self.draw_line(
+                (self.width as f32 - start.x) as i32,
+                (self.height as f32 - start.y) as i32,
+                (self.width as f32 - end.x) as i32,
+                (self.height as f32 - end.y) as i32,
+                color,
+            );
+        }
+    }
+
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+
+        let pixel_index = (y as usize * self.width as usize + x as usize) * 3;
+        Some(Color::RGB(
+            self.pixels[pixel_index],
+            self.pixels[pixel_index + 1],
+            self.pixels[pixel_index + 2],
+        ))
+    }
+
+    // Pixels outside the frame are skipped
+    pub fn draw_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 || color.a == 0 {
+            return;
+        }
+
+        let pixel_index = (y as usize * self.width as usize + x as usize) * 3;
+        let pixel = &mut self.pixels[pixel_index..pixel_index + 3];
+        if color.a == 255 {
+            pixel.copy_from_slice(&[color.r, color.g, color.b]);
+        } else {
+            let alpha = color.a as u32;
+            for (channel, value) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+                *channel = ((value as u32 * alpha + *channel as u32 * (255 - alpha)) / 255) as u8;
+            }
+        }
+    }
+
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        // Bresenham's line algorithm
+        let mut x0 = x0;
+        let mut y0 = y0;
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            self.draw_pixel(x0, y0, color);
+
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+
+    // The outline of a rectangle with its top left corner at (x, y)
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let (right, bottom) = (x + width as i32 - 1, y + height as i32 - 1);
+        self.draw_span(x, right, y, color);
+        if bottom != y {
+            self.draw_span(x, right, bottom, color);
+        }
+        for row in y + 1..bottom {
+            self.draw_pixel(x, row, color);
+            if right != x {
+                self.draw_pixel(right, row, color);
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        for row in y..y + height as i32 {
+            self.draw_span(x, x + width as i32 - 1, row, color);
+        }
+    }
+
+    // Midpoint circle algorithm, every pixel of the outline is drawn once
+    pub fn draw_circle(&mut self, center_x: i32, center_y: i32, radius: u32, color: Color) {
+        let mut x = radius as i32;
+        let mut y = 0;
+        let mut err = 1 - x;
+
+        while x >= y {
+            let mut points = [
+                (x, y),
+                (y, x),
+                (-y, x),
+                (-x, y),
+                (-x, -y),
+                (-y, -x),
+                (y, -x),
+                (x, -y),
+            ];
+            // Where the octants meet the same pixel would be drawn twice
+            points.sort_unstable();
+            for (i, &(offset_x, offset_y)) in points.iter().enumerate() {
+                if i == 0 || points[i - 1] != (offset_x, offset_y) {
+                    self.draw_pixel(center_x + offset_x, center_y + offset_y, color);
+                }
+            }
+
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    // In i64 so squaring a large radius doesn't overflow, only rows on screen are visited
+    pub fn fill_circle(&mut self, center_x: i32, center_y: i32, radius: u32, color: Color) {
+        let (center_x, center_y, radius) = (center_x as i64, center_y as i64, radius as i64);
+        let first_row = (center_y - radius).max(0);
+        let last_row = (center_y + radius).min(self.height as i64 - 1);
+
+        for y in first_row..=last_row {
+            let offset_y = y - center_y;
+            let half_width = ((radius * radius - offset_y * offset_y) as f64).sqrt() as i64;
+            // Clamped just past the edges, so the span still reaches them
+            let start = (center_x - half_width).max(-1);
+            let end = (center_x + half_width).min(self.width as i64);
+            self.draw_span(start as i32, end as i32, y as i32, color);
+        }
+    }
+
+    pub fn draw_triangle(&mut self, points: [(i32, i32); 3], color: Color) {
+        for i in 0..3 {
+            let (x0, y0) = points[i];
+            let (x1, y1) = points[(i + 1) % 3];
+            self.draw_line(x0, y0, x1, y1, color);
+        }
+    }
+
+    // Fills every pixel inside the triangle or on its edges, in either winding order
+    pub fn fill_triangle(&mut self, points: [(i32, i32); 3], color: Color) {
+        let [(x0, y0), (x1, y1), (x2, y2)] = points.map(|(x, y)| (x as i64, y as i64));
+        let area = (x1 - x0) * (y2 - y0) - (y1 - y0) * (x2 - x0);
+        if area == 0 {
+            return;
+        }
+
+        // Only look at the part of the bounding box that is on screen
+        let min_x = x0.min(x1).min(x2).max(0);
+        let max_x = x0.max(x1).max(x2).min(self.width as i64 - 1);
+        let min_y = y0.min(y1).min(y2).max(0);
+        let max_y = y0.max(y1).max(y2).min(self.height as i64 - 1);
+
+        // Which side of the edge from a to b the point is on, the sign flips with the winding
+        let edge = |(ax, ay): (i64, i64), (bx, by): (i64, i64), x: i64, y: i64| {
+            ((bx - ax) * (y - ay) - (by - ay) * (x - ax)) * area.signum()
+        };
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if edge((x0, y0), (x1, y1), x, y) >= 0
+                    && edge((x1, y1), (x2, y2), x, y) >= 0
+                    && edge((x2, y2), (x0, y0), x, y) >= 0
+                {
+                    self.draw_pixel(x as i32, y as i32, color);
+                }
+            }
+        }
+    }
+
+    // Draws a sprite with its top left corner at (x, y)
+    pub fn draw_sprite(&mut self, x: i32, y: i32, sprite: &Sprite) {
+        for sprite_y in 0..sprite.height {
+            for sprite_x in 0..sprite.width {
+                let color = sprite.pixels[(sprite_y * sprite.width + sprite_x) as usize];
+                self.draw_pixel(x + sprite_x as i32, y + sprite_y as i32, color);
+            }
+        }
+    }
+
+    // Text with its top left at (x, y), a new line starts below the first
+    // character of the previous one. Characters the font doesn't have are drawn
+    // as '?'.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, font: &Font, color: Color) {
+        let (glyph_width, glyph_height) = (font.glyph_width as i32, font.glyph_height as i32);
+        for (row, line) in text.lines().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                if let Some(glyph) = font.glyph(character).or_else(|| font.glyph('?')) {
+                    let left = x + column as i32 * glyph_width;
+                    let top = y + row as i32 * glyph_height;
+                    for (index, _) in glyph.iter().enumerate().filter(|(_, set)| **set) {
+                        let index = index as i32;
+                        self.draw_pixel(
+                            left + index % glyph_width,
+                            top + index / glyph_width,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // A horizontal line from x0 to x1, both included
+    fn draw_span(&mut self, x0: i32, x1: i32, y: i32, color: Color) {
+        if y < 0 || y >= self.height as i32 {
+            return;
+        }
+        for x in x0.max(0)..=x1.min(self.width as i32 - 1) {
+            self.draw_pixel(x, y, color);
+        }
+    }
+}
+
+// Calls `plot` with the pixel number and depth of every pixel a projected
+// triangle covers, left to right and top to bottom in projected coordinates
+fn rasterize(
+    width: u32,
+    height: u32,
+    projected_triangle: &Triangle,
+    mut plot: impl FnMut(usize, f32),
+) {
+    // Order projected points from top to bottom by their `y` value
+    let mut ordered_points = projected_triangle.vectors;
+    ordered_points.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap());
+
+    // Deconstruct the sorted points
+    let p0 = &ordered_points[0];
+    let p1 = &ordered_points[1];
+    let p2 = &ordered_points[2];
+
+    // Interpolate x-coordinates and depths along the edges
+    let x01 = interpolate(p0.x, p0.y, p1.x, p1.y);
+    let x12 = interpolate(p1.x, p1.y, p2.x, p2.y);
+    let x02 = interpolate(p0.x, p0.y, p2.x, p2.y);
+    let z01 = interpolate(p0.z, p0.y, p1.z, p1.y);
+    let z12 = interpolate(p1.z, p1.y, p2.z, p2.y);
+    let z02 = interpolate(p0.z, p0.y, p2.z, p2.y);
+
+    // Merge the edges from p0 to p1 and p1 to p2 for the full edge from p0 to p2
+    let mut x_full = x01;
+    x_full.pop(); // Avoid duplicating the middle point
+    x_full.extend(x12);
+    let mut z_full = z01;
+    z_full.pop();
+    z_full.extend(z12);
+
+    // Determine left and right edges based on midpoint comparison
+    let mid_index = x02.len() / 2;
+    let ((x_left, z_left), (x_right, z_right)) = if x02[mid_index] < x_full[mid_index] {
+        ((x02, z02), (x_full, z_full))
+    } else {
+        ((x_full, z_full), (x02, z02))
+    };
+
+    // Ensure we stay within bounds of the interpolation arrays
+    let start_y = p0.y as i32;
+    let end_y = p2.y as i32;
+
+    for y in start_y..end_y {
+        let index = (y - start_y) as usize;
+
+        if index < x_left.len() && index < x_right.len() {
+            let x_start = x_left[index] as i32;
+            let x_end = x_right[index] as i32;
+            let z_step = (z_right[index] - z_left[index]) / (x_end - x_start).max(1) as f32;
+
+            for x in x_start..x_end {
+                let screen_x = (width as i32 - x) as usize;
+                let screen_y = (height as i32 - y) as usize;
+
+                // Bounds checking
+                if screen_x < width as usize && screen_y < height as usize {
+                    let z = z_left[index] + z_step * (x - x_start) as f32;
+                    plot(screen_y * width as usize + screen_x, z);
+                }
+            }
+        }
+    }
+}
+
+// A color from blue at 0 through cyan, green and yellow to red at 1
+fn heat_color(heat: f32) -> Color {
+    let heat = heat.clamp(0.0, 1.0) * 4.0;
+    let ramp = |value: f32| (value.clamp(0.0, 1.0) * 255.0) as u8;
+    Color::RGB(
+        ramp(heat - 2.0),
+        ramp(heat.min(4.0 - heat)),
+        ramp(2.0 - heat),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::vector_3d::Vector3D;
+
+    fn lit_pixels(frame_buffer: &FrameBuffer) -> usize {
+        frame_buffer
+            .pixels
+            .chunks(3)
+            .filter(|pixel| pixel.iter().any(|channel| *channel > 0))
+            .count()
+    }
+
+    #[test]
+    fn shapes_are_clipped_to_the_frame() {
+        let mut frame_buffer = FrameBuffer::new(10, 10);
+        frame_buffer.fill_rect(-5, 8, 20, 20, Color::WHITE);
+        assert_eq!(lit_pixels(&frame_buffer), 20);
+
+        frame_buffer.clear();
+        frame_buffer.draw_rect(0, 0, 10, 10, Color::WHITE);
+        assert_eq!(lit_pixels(&frame_buffer), 36);
+    }
+
+    #[test]
+    fn alpha_is_blended() {
+        let mut frame_buffer = FrameBuffer::new(1, 1);
+        frame_buffer.draw_pixel(0, 0, Color::RGB(200, 100, 0));
+        frame_buffer.draw_pixel(0, 0, Color::RGBA(0, 0, 255, 51));
+        assert_eq!(frame_buffer.get_pixel(0, 0), Some(Color::RGB(160, 80, 51)));
+
+        let mut sprite = Sprite::new(2, 1);
+        sprite.set_pixel(1, 0, Color::RGB(0, 255, 0));
+        frame_buffer.draw_sprite(-1, 0, &sprite);
+        assert_eq!(frame_buffer.get_pixel(0, 0), Some(Color::RGB(0, 255, 0)));
+    }
+
+    #[test]
+    fn circles() {
+        // Half transparent, so a pixel drawn twice would come out brighter
+        let mut outline = FrameBuffer::new(20, 20);
+        outline.draw_circle(10, 10, 5, Color::RGBA(255, 255, 255, 128));
+        let lit: Vec<&[u8]> = outline
+            .pixels
+            .chunks(3)
+            .filter(|pixel| pixel[0] > 0)
+            .collect();
+        assert!(!lit.is_empty());
+        assert!(lit.iter().all(|pixel| *pixel == lit[0]));
+
+        let mut filled = FrameBuffer::new(20, 20);
+        filled.fill_circle(10, 10, 1, Color::WHITE);
+        assert_eq!(lit_pixels(&filled), 5);
+
+        // Far larger than the frame, squaring the radius would overflow an i32
+        filled.fill_circle(10, 10, 100_000, Color::WHITE);
+        assert_eq!(lit_pixels(&filled), 400);
+    }
+
+    #[test]
+    fn heatmap_without_a_max_count() {
+        let mut frame_buffer = FrameBuffer::new(2, 1);
+        frame_buffer.draw_heatmap(&[0, 3], 0);
+        assert_eq!(frame_buffer.get_pixel(0, 0), Some(Color::BLACK));
+        assert_eq!(frame_buffer.get_pixel(1, 0), Some(Color::RED));
+    }
+
+    #[test]
+    fn triangles_fill_the_same_pixels_in_both_windings() {
+        let points = [(1, 1), (8, 2), (3, 9)];
+        let mut clockwise = FrameBuffer::new(10, 10);
+        clockwise.fill_triangle(points, Color::WHITE);
+        let mut counter_clockwise = FrameBuffer::new(10, 10);
+        counter_clockwise.fill_triangle([points[0], points[2], points[1]], Color::WHITE);
+
+        assert!(lit_pixels(&clockwise) > 0);
+        assert_eq!(clockwise.pixels, counter_clockwise.pixels);
+    }
+
+    #[test]
+    fn text_is_drawn_with_the_font() {
+        let font = Font::built_in();
+        let mut frame_buffer = FrameBuffer::new(20, 20);
+        frame_buffer.draw_text(2, 2, "|\n|", &font, Color::WHITE);
+        let lit = lit_pixels(&frame_buffer);
+        let bar = font
+            .glyph('|')
+            .unwrap()
+            .iter()
+            .filter(|pixel| **pixel)
+            .count();
+        assert_eq!(lit, bar * 2);
+
+        // Unknown characters look like a question mark
+        let mut unknown = FrameBuffer::new(8, 8);
+        unknown.draw_text(0, 0, "\u{2603}", &font, Color::WHITE);
+        let mut question_mark = FrameBuffer::new(8, 8);
+        question_mark.draw_text(0, 0, "?", &font, Color::WHITE);
+        assert_eq!(unknown.pixels, question_mark.pixels);
+    }
+
+    #[test]
+    fn overlapping_triangles_show_depth_and_overdraw() {
+        // Projected coordinates, the near triangle is drawn first and hides the far one
+        let triangle = |z: f32, color: Color| {
+            let mut triangle = Triangle::new_from_vectors([
+                Vector3D::from_coords(1.0, 1.0, z),
+                Vector3D::from_coords(9.0, 1.0, z),
+                Vector3D::from_coords(1.0, 9.0, z),
+            ]);
+            triangle.base_color = color;
+            triangle
+        };
+        let near = triangle(0.5, Color::WHITE);
+        let far = triangle(0.9, Color::RED);
+        let mut frame_buffer = FrameBuffer::new(10, 10);
+        frame_buffer.draw_filled_triangle(&near);
+        frame_buffer.draw_filled_triangle(&far);
+
+        let drawn: Vec<f32> = frame_buffer
+            .depth
+            .iter()
+            .copied()
+            .filter(|z| z.is_finite())
+            .collect();
+        assert!(!drawn.is_empty());
+        assert!(drawn.iter().all(|z| (z - 0.5).abs() < 1e-4));
+        assert_eq!(lit_pixels(&frame_buffer), drawn.len());
+        assert!(frame_buffer
+            .pixels
+            .chunks(3)
+            .all(|pixel| pixel == [0, 0, 0] || pixel == [255, 255, 255]));
+
+        // A triangle in front of part of the other one only replaces that part
+        let mut closer = triangle(0.2, Color::RED);
+        closer.vectors[1].x = 5.0;
+        closer.vectors[2].y = 5.0;
+        frame_buffer.draw_filled_triangle(&closer);
+        let red = frame_buffer
+            .depth
+            .iter()
+            .filter(|z| (*z - 0.2).abs() < 1e-4)
+            .count();
+        assert!(red > 0 && red < drawn.len());
+        assert_eq!(
+            frame_buffer
+                .pixels
+                .chunks(3)
+                .filter(|pixel| *pixel == [255, 0, 0])
+                .count(),
+            red
+        );
+
+        let mut counts = vec![0; 100];
+        frame_buffer.count_overdraw(&far, &mut counts);
+        frame_buffer.count_overdraw(&near, &mut counts);
+        assert_eq!(
+            counts.iter().filter(|count| **count == 2).count(),
+            drawn.len()
+        );
+
+        frame_buffer.draw_heatmap(&counts, 2);
+        assert_eq!(lit_pixels(&frame_buffer), drawn.len());
+    }
+}
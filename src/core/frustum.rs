@@ -0,0 +1,153 @@
+use super::{aabb::Aabb, math::vector_dot_product, matrix_4x4::Matrix4X4, vector_3d::Vector3D};
+
+// Plane in the form dot(normal, point) + distance = 0, with the normal pointing inside
+pub struct Plane {
+    pub normal: Vector3D,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn from_point_normal(point: &Vector3D, normal: &Vector3D) -> Plane {
+        let normal = normal.from_normalise();
+        let distance = -vector_dot_product(&normal, point);
+        Plane { normal, distance }
+    }
+
+    // Builds a plane from the coefficients (a, b, c, d) of ax + by + cz + d = 0
+    pub fn from_coefficients(a: f32, b: f32, c: f32, d: f32) -> Plane {
+        let length = (a * a + b * b + c * c).sqrt();
+        Plane {
+            normal: Vector3D::from_coords(a / length, b / length, c / length),
+            distance: d / length,
+        }
+    }
+
+    pub fn signed_distance(&self, point: &Vector3D) -> f32 {
+        vector_dot_product(&self.normal, point) + self.distance
+    }
+}
+
+#[derive(PartialEq)]
+pub enum Containment {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+// Convex volume bounded by planes, usually the 6 planes of the view volume
+pub struct Frustum {
+    pub planes: Vec<Plane>,
+}
+
+impl Frustum {
+    pub fn from_planes(planes: Vec<Plane>) -> Frustum {
+        Frustum { planes }
+    }
+
+    // Extracts the 6 world space planes of the view volume from the view and
+    // projection matrices (Gribb & Hartmann). The projection maps depth to 0..1.
+    pub fn from_matrices(view_matrix: &Matrix4X4, projection_matrix: &Matrix4X4) -> Frustum {
+        let matrix = view_matrix * projection_matrix;
+        let column = |i: usize| {
+            [
+                matrix.content[0][i],
+                matrix.content[1][i],
+                matrix.content[2][i],
+                matrix.content[3][i],
+            ]
+        };
+        let (x, y, z, w) = (column(0), column(1), column(2), column(3));
+        let plane = |c: [f32; 4]| Plane::from_coefficients(c[0], c[1], c[2], c[3]);
+        let add = |a: [f32; 4], b: [f32; 4]| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]];
+        let sub = |a: [f32; 4], b: [f32; 4]| [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]];
+
+        Frustum::from_planes(vec![
+            plane(add(w, x)), // Left
+            plane(sub(w, x)), // Right
+            plane(add(w, y)), // Bottom
+            plane(sub(w, y)), // Top
+            plane(z),         // Near
+            plane(sub(w, z)), // Far
+        ])
+    }
+
+    // Moves the frustum from world space into the space of an object with the given
+    // world matrix, so it can be tested against bounds in object space
+    pub fn to_object_space(&self, world_matrix: &Matrix4X4) -> Frustum {
+        let m = &world_matrix.content;
+        Frustum::from_planes(
+            self.planes
+                .iter()
+                .map(|plane| {
+                    let p = [
+                        plane.normal.x,
+                        plane.normal.y,
+                        plane.normal.z,
+                        plane.distance,
+                    ];
+                    let row = |r: usize| {
+                        m[r][0] * p[0] + m[r][1] * p[1] + m[r][2] * p[2] + m[r][3] * p[3]
+                    };
+                    Plane::from_coefficients(row(0), row(1), row(2), row(3))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn intersects_sphere(&self, center: &Vector3D, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(center) >= -radius)
+    }
+
+    pub fn classify_aabb(&self, aabb: &Aabb) -> Containment {
+        let mut result = Containment::Inside;
+
+        for plane in &self.planes {
+            // The corners furthest along and against the plane normal
+            let positive = Vector3D::from_coords(
+                if plane.normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            let negative = Vector3D::from_coords(
+                if plane.normal.x >= 0.0 {
+                    aabb.min.x
+                } else {
+                    aabb.max.x
+                },
+                if plane.normal.y >= 0.0 {
+                    aabb.min.y
+                } else {
+                    aabb.max.y
+                },
+                if plane.normal.z >= 0.0 {
+                    aabb.min.z
+                } else {
+                    aabb.max.z
+                },
+            );
+
+            if plane.signed_distance(&positive) < 0.0 {
+                return Containment::Outside;
+            }
+            if plane.signed_distance(&negative) < 0.0 {
+                result = Containment::Intersecting;
+            }
+        }
+
+        result
+    }
+}
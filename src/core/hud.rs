@@ -0,0 +1,75 @@
+use sdl2::pixels::Color;
+
+use super::{camera::Camera, font::Font, frame_buffer::FrameBuffer, renderer::FrameStats};
+
+// Frame statistics drawn in the top left corner of the frame. It is part of the
+// frame buffer, so it also shows up in screenshots.
+pub struct Hud {
+    pub visible: bool,
+    pub font: Font,
+    // Smoothed seconds per frame, so the numbers can be read
+    frame_time: f32,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            visible: false,
+            font: Font::built_in(),
+            frame_time: 0.0,
+        }
+    }
+
+    // Adds the seconds the last frame took
+    pub fn update(&mut self, frame_time: f32) {
+        if self.frame_time == 0.0 {
+            self.frame_time = frame_time;
+        } else {
+            self.frame_time = self.frame_time * 0.9 + frame_time * 0.1;
+        }
+    }
+
+    pub fn text(&self, stats: &FrameStats, camera: &Camera, render_mode: &str) -> String {
+        let fps = if self.frame_time > 0.0 {
+            1.0 / self.frame_time
+        } else {
+            0.0
+        };
+        let position = camera.position;
+        let (pitch, yaw, roll) = camera.orientation.to_euler();
+        format!(
+            "{:.1} FPS, {:.2} ms\n\
+             Triangles: {} submitted, {} clipped, {} drawn\n\
+             Camera: ({:.2}, {:.2}, {:.2})\n\
+             Yaw {:.1}, pitch {:.1}, roll {:.1}\n\
+             Render mode: {}",
+            fps,
+            self.frame_time * 1000.0,
+            stats.triangles_in,
+            stats.triangles_clipped,
+            stats.triangles_out,
+            position.x,
+            position.y,
+            position.z,
+            yaw.to_degrees(),
+            pitch.to_degrees(),
+            roll.to_degrees(),
+            render_mode
+        )
+    }
+
+    pub fn draw(
+        &self,
+        frame_buffer: &mut FrameBuffer,
+        stats: &FrameStats,
+        camera: &Camera,
+        render_mode: &str,
+    ) {
+        let text = self.text(stats, camera, render_mode);
+        let (width, height) = self.font.text_size(&text);
+
+        // A dark background keeps the text readable on any scene
+        frame_buffer.fill_rect(4, 4, width + 8, height + 8, Color::RGBA(0, 0, 0, 160));
+        frame_buffer.draw_text(8, 8, &text, &self.font, Color::WHITE);
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use super::{mesh::Mesh, triangle::Triangle, vector_3d::Vector3D};
+
+// Shared-vertex view of a mesh, for algorithms that need connectivity.
+// Faces are stored in the same order as the triangles of the source mesh.
+pub struct IndexedMesh {
+    pub vertices: Vec<Vector3D>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+impl IndexedMesh {
+    pub fn from_mesh(mesh: &Mesh, weld_distance: f32) -> IndexedMesh {
+        let mut welder = VertexWelder::new(weld_distance);
+        let faces = mesh
+            .triangles
+            .iter()
+            .map(|triangle| {
+                [
+                    welder.index_of(&triangle.vectors[0]),
+                    welder.index_of(&triangle.vectors[1]),
+                    welder.index_of(&triangle.vectors[2]),
+                ]
+            })
+            .collect();
+
+        IndexedMesh {
+            vertices: welder.vertices,
+            faces,
+        }
+    }
+
+    pub fn to_mesh(&self) -> Mesh {
+        Mesh {
+            triangles: self
+                .faces
+                .iter()
+                .map(|face| Triangle::from_vectors(&self.vertices, *face))
+                .collect(),
+            polygons: vec![],
+        }
+    }
+
+    // Maps every undirected edge (lowest index first) to the faces using it
+    pub fn edge_faces(&self) -> HashMap<(usize, usize), Vec<usize>> {
+        let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+
+        for (face_index, face) in self.faces.iter().enumerate() {
+            for i in 0..3 {
+                let a = face[i];
+                let b = face[(i + 1) % 3];
+                if a != b {
+                    edges
+                        .entry((a.min(b), a.max(b)))
+                        .or_default()
+                        .push(face_index);
+                }
+            }
+        }
+
+        edges
+    }
+}
+
+// Returns true if the face walks along the edge from `a` to `b`
+pub fn face_has_directed_edge(face: &[usize; 3], a: usize, b: usize) -> bool {
+    (0..3).any(|i| face[i] == a && face[(i + 1) % 3] == b)
+}
+
+// Hands out one index per unique position. Vertices closer together than
+// `weld_distance` are merged into one, a distance of 0.0 only merges vertices
+// with exactly the same position.
+pub struct VertexWelder {
+    pub vertices: Vec<Vector3D>,
+    weld_distance: f32,
+    cell_size: f32,
+    grid: HashMap<(i64, i64, i64), Vec<usize>>,
+}
+
+impl VertexWelder {
+    pub fn new(weld_distance: f32) -> VertexWelder {
+        VertexWelder {
+            vertices: vec![],
+            weld_distance,
+            cell_size: if weld_distance > 0.0 {
+                weld_distance
+            } else {
+                f32::EPSILON
+            },
+            grid: HashMap::new(),
+        }
+    }
+
+    pub fn index_of(&mut self, vector: &Vector3D) -> usize {
+        let cell = (
+            (vector.x / self.cell_size).floor() as i64,
+            (vector.y / self.cell_size).floor() as i64,
+            (vector.z / self.cell_size).floor() as i64,
+        );
+
+        match self.find(cell, vector) {
+            Some(index) => index,
+            None => {
+                self.vertices.push(*vector);
+                self.grid
+                    .entry(cell)
+                    .or_default()
+                    .push(self.vertices.len() - 1);
+                self.vertices.len() - 1
+            }
+        }
+    }
+
+    fn find(&self, cell: (i64, i64, i64), vector: &Vector3D) -> Option<usize> {
+        let max_distance_squared = self.weld_distance * self.weld_distance;
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(candidates) = self.grid.get(&(cell.0 + dx, cell.1 + dy, cell.2 + dz))
+                    else {
+                        continue;
+                    };
+
+                    for &index in candidates {
+                        let difference = self.vertices[index] - vector;
+                        let distance_squared = difference.x * difference.x
+                            + difference.y * difference.y
+                            + difference.z * difference.z;
+                        if distance_squared <= max_distance_squared {
+                            return Some(index);
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_helpers::{tetrahedron, vector};
+    use proptest::prelude::*;
+
+    #[test]
+    fn shares_vertices_between_faces() {
+        let mesh = tetrahedron();
+        let indexed = IndexedMesh::from_mesh(&mesh, 0.0);
+
+        assert_eq!(indexed.vertices.len(), 4);
+        assert_eq!(indexed.faces.len(), 4);
+        for (face, triangle) in indexed.faces.iter().zip(&mesh.triangles) {
+            for (index, vector) in face.iter().zip(&triangle.vectors) {
+                assert_eq!(indexed.vertices[*index], *vector);
+            }
+        }
+
+        let round_trip = indexed.to_mesh();
+        for (a, b) in round_trip.triangles.iter().zip(&mesh.triangles) {
+            assert_eq!(a.vectors, b.vectors);
+        }
+    }
+
+    #[test]
+    fn edge_faces_of_a_closed_mesh() {
+        let indexed = IndexedMesh::from_mesh(&tetrahedron(), 0.0);
+        let edge_faces = indexed.edge_faces();
+
+        assert_eq!(edge_faces.len(), 6);
+        for (&(a, b), faces) in &edge_faces {
+            assert!(a < b);
+            assert_eq!(faces.len(), 2);
+            // Consistently wound neighbours walk the edge in opposite directions
+            let first = face_has_directed_edge(&indexed.faces[faces[0]], a, b);
+            let second = face_has_directed_edge(&indexed.faces[faces[1]], a, b);
+            assert_ne!(first, second);
+        }
+    }
+
+    #[test]
+    fn edge_faces_skip_collapsed_edges() {
+        let indexed = IndexedMesh {
+            vertices: vec![Vector3D::new(), Vector3D::from_coords(1.0, 0.0, 0.0)],
+            faces: vec![[0, 0, 1]],
+        };
+        let edge_faces = indexed.edge_faces();
+
+        assert_eq!(edge_faces.len(), 1);
+        assert_eq!(edge_faces[&(0, 1)], vec![0, 0]);
+    }
+
+    #[test]
+    fn exact_welder_only_merges_equal_positions() {
+        let mut welder = VertexWelder::new(0.0);
+        let a = welder.index_of(&Vector3D::from_coords(1.0, 2.0, 3.0));
+        let b = welder.index_of(&Vector3D::from_coords(1.0, 2.0, 3.0 + 1e-6));
+        let c = welder.index_of(&Vector3D::from_coords(1.0, 2.0, 3.0));
+
+        assert_ne!(a, b);
+        assert_eq!(a, c);
+        assert_eq!(welder.vertices.len(), 2);
+    }
+
+    #[test]
+    fn welder_merges_across_grid_cells() {
+        let mut welder = VertexWelder::new(0.1);
+        // Both sides of the cell border at 0.0
+        let a = welder.index_of(&Vector3D::from_coords(-0.02, 0.0, 0.0));
+        let b = welder.index_of(&Vector3D::from_coords(0.05, 0.0, 0.0));
+        let c = welder.index_of(&Vector3D::from_coords(0.2, 0.0, 0.0));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // The first position is kept
+        assert_eq!(welder.vertices[a], Vector3D::from_coords(-0.02, 0.0, 0.0));
+    }
+
+    proptest! {
+        #[test]
+        fn welded_vertices_are_within_the_weld_distance(
+            vectors in prop::collection::vec(vector(), 1..50),
+            weld_distance in 0.0f32..20.0,
+        ) {
+            let mut welder = VertexWelder::new(weld_distance);
+            for vector in &vectors {
+                let index = welder.index_of(vector);
+                let difference = welder.vertices[index] - vector;
+                let distance_squared = difference.x * difference.x
+                    + difference.y * difference.y
+                    + difference.z * difference.z;
+                prop_assert!(distance_squared <= weld_distance * weld_distance);
+            }
+
+            // Vertices that were kept apart are further away from each other
+            for (i, a) in welder.vertices.iter().enumerate() {
+                for b in &welder.vertices[i + 1..] {
+                    let difference = a - b;
+                    let distance_squared = difference.x * difference.x
+                        + difference.y * difference.y
+                        + difference.z * difference.z;
+                    prop_assert!(distance_squared > weld_distance * weld_distance);
+                }
+            }
+        }
+    }
+}
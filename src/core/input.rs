@@ -0,0 +1,362 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+use sdl2::{
+    event::Event,
+    keyboard::{Keycode, Scancode},
+    mouse::MouseButton,
+};
+
+// Everything the viewer can be told to do, independent of the keys used for it
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeLeft,
+    StrafeRight,
+    Ascend,
+    Descend,
+    TurnLeft,
+    TurnRight,
+    RollLeft,
+    RollRight,
+    Pan,
+    Select,
+    SubdivideMore,
+    SubdivideLess,
+    NextCamera,
+    ToggleProjection,
+    ToggleOrbit,
+    NextRenderMode,
+    NextCullMode,
+    ToggleHud,
+    Screenshot,
+    // Marks the frame in a recording, replays save a screenshot of it
+    MarkFrame,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 23] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::Ascend,
+        Action::Descend,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::RollLeft,
+        Action::RollRight,
+        Action::Pan,
+        Action::Select,
+        Action::SubdivideMore,
+        Action::SubdivideLess,
+        Action::NextCamera,
+        Action::ToggleProjection,
+        Action::ToggleOrbit,
+        Action::NextRenderMode,
+        Action::NextCullMode,
+        Action::ToggleHud,
+        Action::Screenshot,
+        Action::MarkFrame,
+        Action::Quit,
+    ];
+
+    // Name used in the bindings file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBackward => "move_backward",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::Ascend => "ascend",
+            Action::Descend => "descend",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::RollLeft => "roll_left",
+            Action::RollRight => "roll_right",
+            Action::Pan => "pan",
+            Action::Select => "select",
+            Action::SubdivideMore => "subdivide_more",
+            Action::SubdivideLess => "subdivide_less",
+            Action::NextCamera => "next_camera",
+            Action::ToggleProjection => "toggle_projection",
+            Action::ToggleOrbit => "toggle_orbit",
+            Action::NextRenderMode => "next_render_mode",
+            Action::NextCullMode => "next_cull_mode",
+            Action::ToggleHud => "toggle_hud",
+            Action::Screenshot => "screenshot",
+            Action::MarkFrame => "mark_frame",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+// Something that can trigger an action
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Binding {
+    // The key with this label, which moves around with the keyboard layout
+    Key(Keycode),
+    // The key at this position on a US keyboard, whatever its label. On AZERTY
+    // keyboards `W` is the key labelled `Z`.
+    Scancode(Scancode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    // Parses `W`, `scancode:W` or `mouse:Left`. Key names are the ones SDL uses,
+    // like `PageUp`, `Left` or `Escape`.
+    pub fn from_name(name: &str) -> Option<Binding> {
+        let name = name.trim();
+        if let Some(scancode) = name.strip_prefix("scancode:") {
+            return Scancode::from_name(scancode.trim()).map(Binding::Scancode);
+        }
+        if let Some(button) = name.strip_prefix("mouse:") {
+            let button = match button.trim().to_lowercase().as_str() {
+                "left" => MouseButton::Left,
+                "middle" => MouseButton::Middle,
+                "right" => MouseButton::Right,
+                "x1" => MouseButton::X1,
+                "x2" => MouseButton::X2,
+                _ => return None,
+            };
+            return Some(Binding::Mouse(button));
+        }
+        Keycode::from_name(name).map(Binding::Key)
+    }
+}
+
+// Maps keys and mouse buttons to actions, and keeps track of which are held down
+pub struct InputMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+    pub mouse_sensitivity: f32,
+    pub invert_y: bool,
+    pressed: HashSet<Binding>,
+}
+
+impl InputMap {
+    // The default bindings. Movement uses key positions, so WASD is ZQSD on AZERTY.
+    pub fn new() -> InputMap {
+        let mut input_map = InputMap {
+            bindings: HashMap::new(),
+            mouse_sensitivity: 0.2,
+            invert_y: false,
+            pressed: HashSet::new(),
+        };
+
+        let defaults = [
+            (Action::MoveForward, Binding::Scancode(Scancode::W)),
+            (Action::MoveBackward, Binding::Scancode(Scancode::S)),
+            (Action::StrafeLeft, Binding::Scancode(Scancode::A)),
+            (Action::StrafeRight, Binding::Scancode(Scancode::D)),
+            (Action::Ascend, Binding::Key(Keycode::UP)),
+            (Action::Descend, Binding::Key(Keycode::DOWN)),
+            (Action::TurnLeft, Binding::Key(Keycode::LEFT)),
+            (Action::TurnRight, Binding::Key(Keycode::RIGHT)),
+            (Action::RollLeft, Binding::Scancode(Scancode::Q)),
+            (Action::RollRight, Binding::Scancode(Scancode::E)),
+            (Action::Pan, Binding::Mouse(MouseButton::Middle)),
+            (Action::Select, Binding::Mouse(MouseButton::Left)),
+            (Action::SubdivideMore, Binding::Key(Keycode::PAGEUP)),
+            (Action::SubdivideLess, Binding::Key(Keycode::PAGEDOWN)),
+            (Action::NextCamera, Binding::Key(Keycode::C)),
+            (Action::ToggleProjection, Binding::Key(Keycode::P)),
+            (Action::ToggleOrbit, Binding::Key(Keycode::O)),
+            (Action::NextRenderMode, Binding::Key(Keycode::F)),
+            (Action::NextCullMode, Binding::Key(Keycode::B)),
+            (Action::ToggleHud, Binding::Key(Keycode::H)),
+            (Action::Screenshot, Binding::Key(Keycode::F12)),
+            (Action::MarkFrame, Binding::Key(Keycode::F9)),
+            (Action::Quit, Binding::Key(Keycode::ESCAPE)),
+        ];
+        for (action, binding) in defaults {
+            input_map.bind(action, vec![binding]);
+        }
+
+        input_map
+    }
+
+    // Loads a bindings file, see `from_config`
+    pub fn from_file(filename: &str) -> Result<InputMap, String> {
+        let config = fs::read_to_string(filename)
+            .map_err(|err| format!("Error reading {}: {}", filename, err))?;
+        InputMap::from_config(&config).map_err(|err| format!("{}: {}", filename, err))
+    }
+
+    // Every line is `name = value`, empty lines and lines starting with `#` are
+    // skipped. Actions take a comma separated list of bindings, and replace the
+    // default bindings of that action. The settings are `mouse_sensitivity` and
+    // `invert_y`.
+    pub fn from_config(config: &str) -> Result<InputMap, String> {
+        let mut input_map = InputMap::new();
+
+        for (line_number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| format!("line {}: {}", line_number + 1, message);
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected `name = value`, got {}", line)))?;
+            let (name, value) = (name.trim(), value.trim());
+
+            match name {
+                "mouse_sensitivity" => {
+                    input_map.mouse_sensitivity = value
+                        .parse()
+                        .map_err(|_| error(format!("invalid mouse sensitivity: {}", value)))?;
+                }
+                "invert_y" => {
+                    input_map.invert_y = value
+                        .parse()
+                        .map_err(|_| error(format!("expected true or false, got {}", value)))?;
+                }
+                _ => {
+                    let action = Action::from_name(name)
+                        .ok_or_else(|| error(format!("unknown action: {}", name)))?;
+                    let bindings = value
+                        .split(',')
+                        .filter(|binding| !binding.trim().is_empty())
+                        .map(|binding| {
+                            Binding::from_name(binding)
+                                .ok_or_else(|| error(format!("unknown key: {}", binding.trim())))
+                        })
+                        .collect::<Result<Vec<Binding>, String>>()?;
+                    input_map.bind(action, bindings);
+                }
+            }
+        }
+
+        Ok(input_map)
+    }
+
+    pub fn bind(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], |bindings| bindings)
+    }
+
+    // Updates which keys and buttons are held down. Returns the actions that were
+    // just pressed, key repeats don't count.
+    pub fn handle_event(&mut self, event: &Event) -> Vec<Action> {
+        let pressed: Vec<Binding> = match *event {
+            Event::KeyDown {
+                keycode,
+                scancode,
+                repeat: false,
+                ..
+            } => keycode
+                .map(Binding::Key)
+                .into_iter()
+                .chain(scancode.map(Binding::Scancode))
+                .collect(),
+            Event::KeyUp {
+                keycode, scancode, ..
+            } => {
+                if let Some(keycode) = keycode {
+                    self.pressed.remove(&Binding::Key(keycode));
+                }
+                if let Some(scancode) = scancode {
+                    self.pressed.remove(&Binding::Scancode(scancode));
+                }
+                vec![]
+            }
+            Event::MouseButtonDown { mouse_btn, .. } => vec![Binding::Mouse(mouse_btn)],
+            Event::MouseButtonUp { mouse_btn, .. } => {
+                self.pressed.remove(&Binding::Mouse(mouse_btn));
+                vec![]
+            }
+            _ => vec![],
+        };
+
+        self.pressed.extend(&pressed);
+        Action::ALL
+            .into_iter()
+            .filter(|action| {
+                self.bindings(*action)
+                    .iter()
+                    .any(|binding| pressed.contains(binding))
+            })
+            .collect()
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| self.pressed.contains(binding))
+    }
+
+    // Mouse movement turned into camera rotation, with sensitivity and invert-Y applied
+    pub fn mouse_motion(&self, rel_x: i32, rel_y: i32) -> (f32, f32) {
+        let rel_y = if self.invert_y { -rel_y } else { rel_y };
+        (
+            rel_x as f32 * self.mouse_sensitivity,
+            rel_y as f32 * self.mouse_sensitivity,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_action_has_a_name() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+        }
+    }
+
+    #[test]
+    fn config_overrides_defaults() {
+        let config = "# comment\n\nselect = mouse:Right, mouse:X1\nmouse_sensitivity = 0.5\ninvert_y = true\n";
+        let input_map = InputMap::from_config(config).unwrap();
+
+        assert_eq!(
+            input_map.bindings(Action::Select),
+            [
+                Binding::Mouse(MouseButton::Right),
+                Binding::Mouse(MouseButton::X1)
+            ]
+        );
+        assert_eq!(
+            input_map.bindings(Action::Pan),
+            [Binding::Mouse(MouseButton::Middle)]
+        );
+        assert_eq!(input_map.mouse_motion(2, 4), (1.0, -2.0));
+    }
+
+    #[test]
+    fn bindings_file_lists_the_defaults() {
+        let config = include_str!("../../bindings.cfg");
+        let input_map = InputMap::from_config(config).unwrap();
+        let defaults = InputMap::new();
+
+        for action in Action::ALL {
+            let line = format!("\n{} = ", action.name());
+            assert!(config.contains(&line), "{} is missing", action.name());
+            assert_eq!(input_map.bindings(action), defaults.bindings(action));
+        }
+        assert_eq!(input_map.mouse_sensitivity, defaults.mouse_sensitivity);
+        assert_eq!(input_map.invert_y, defaults.invert_y);
+    }
+
+    #[test]
+    fn config_errors_name_the_line() {
+        let error = InputMap::from_config("invert_y = true\nfly = mouse:Left").err();
+        assert_eq!(error.as_deref(), Some("line 2: unknown action: fly"));
+        assert!(InputMap::from_config("invert_y = maybe").is_err());
+        assert!(InputMap::from_config("select mouse:Left").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use super::{bvh::Bvh, mesh::Mesh, vector_3d::Vector3D, vertex_batch::VertexBatch};
+
+// Roughly how many pixels a triangle should cover before a coarser level is used
+const PIXELS_PER_TRIANGLE: f32 = 8.0;
+
+// A mesh with progressively simplified versions of itself. Level 0 is the
+// original mesh, every next level has about half the triangles.
+pub struct LodChain {
+    pub levels: Vec<Mesh>,
+    // Bounding volume hierarchy for every level
+    pub bvhs: Vec<Bvh>,
+    // Vertices of every level, laid out for fast transforming
+    pub vertex_batches: Vec<VertexBatch>,
+    pub center: Vector3D,
+    pub radius: f32,
+}
+
+impl LodChain {
+    pub fn new(mesh: Mesh, level_count: usize) -> LodChain {
+        let (center, radius) = mesh.bounding_sphere();
+        let mut levels = vec![mesh];
+
+        while levels.len() < level_count {
+            let previous = levels.last().unwrap();
+            let target = previous.triangles.len() / 2;
+            if target < 4 {
+                break;
+            }
+
+            levels.push(previous.simplify(target, f32::INFINITY));
+        }
+
+        // Triangles are stored in BVH order, so the triangles of a visible part of
+        // the mesh can be transformed together
+        let mut bvhs: Vec<Bvh> = levels.iter().map(Bvh::new).collect();
+        for (bvh, level) in bvhs.iter_mut().zip(&mut levels) {
+            bvh.reorder_mesh(level);
+        }
+
+        LodChain {
+            vertex_batches: levels.iter().map(VertexBatch::from_mesh).collect(),
+            bvhs,
+            levels,
+            center,
+            radius,
+        }
+    }
+
+    // Picks the coarsest level that still has enough triangles for the
+    // projected size (in pixels) of the bounding sphere. Returns the level index.
+    pub fn select(&self, projected_diameter: f32) -> usize {
+        let wanted_triangles = projected_diameter * projected_diameter / PIXELS_PER_TRIANGLE;
+
+        self.levels
+            .iter()
+            .rposition(|level| level.triangles.len() as f32 >= wanted_triangles)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_helpers::tetrahedron;
+
+    fn ball() -> Mesh {
+        tetrahedron().subdivide().subdivide().subdivide()
+    }
+
+    #[test]
+    fn every_level_halves_the_triangles() {
+        let chain = LodChain::new(ball(), 4);
+        let counts: Vec<usize> = chain
+            .levels
+            .iter()
+            .map(|level| level.triangles.len())
+            .collect();
+
+        assert_eq!(counts[0], 256);
+        assert_eq!(counts.len(), 4);
+        for pair in counts.windows(2) {
+            assert!(pair[1] <= pair[0] / 2);
+        }
+        assert_eq!(chain.bvhs.len(), 4);
+        assert_eq!(chain.vertex_batches.len(), 4);
+        for (level, batch) in chain.levels.iter().zip(&chain.vertex_batches) {
+            assert_eq!(batch.len(), level.triangles.len() * 3);
+        }
+    }
+
+    #[test]
+    fn stops_before_levels_get_too_small() {
+        let chain = LodChain::new(tetrahedron(), 4);
+        assert_eq!(chain.levels.len(), 1);
+    }
+
+    #[test]
+    fn small_objects_use_coarse_levels() {
+        let chain = LodChain::new(ball(), 4);
+
+        assert_eq!(chain.select(10_000.0), 0);
+        assert_eq!(chain.select(0.0), 3);
+        // 28 pixels across wants about 98 triangles
+        assert_eq!(chain.select(28.0), 1);
+    }
+}
@@ -10,13 +10,13 @@ pub fn interpolate(x_start: f32, y_start: f32, x_end: f32, y_end: f32) -> Vec<f3
     }
 
     let slope = (x_end - x_start) / (y_end - y_start);
-    let mut x = x_start;
 
-    let step = if y_end > y_start { 1 } else { -1 };
-    let range = (y_start as i32..=y_end as i32).map(|v| v as f32);
-    for _ in range.step_by(step as usize) {
-        result.push(x);
-        x += slope * step as f32; // Update x according to the direction
+    // One value for every whole y between start and end, in either direction
+    let step = if y_end > y_start { 1.0 } else { -1.0 };
+    let count = (y_end as i32 - y_start as i32).unsigned_abs() as usize + 1;
+    for i in 0..count {
+        // Computed from the start every time, so rounding errors don't add up
+        result.push(x_start + slope * step * i as f32);
     }
 
     result
@@ -52,6 +52,258 @@ pub fn intersect_plane(
     let bd: f32 = vector_dot_product(&line_end, &plane_n);
     let t: f32 = (-plane_d - ad) / (bd - ad);
     let line_start_to_end: Vector3D = line_end - line_start;
-    let line_to_intersect: Vector3D = &line_start_to_end * t;
-    return line_start + &line_to_intersect;
+    let line_to_intersect: Vector3D = line_start_to_end * t;
+    return line_start + line_to_intersect;
+}
+
+// Möller–Trumbore ray/triangle intersection. Returns the distance along the ray
+// and the barycentric coordinates (u, v) of the hit, for hits in front of the origin.
+pub fn ray_triangle_intersection(
+    ray_origin: &Vector3D,
+    ray_direction: &Vector3D,
+    v0: &Vector3D,
+    v1: &Vector3D,
+    v2: &Vector3D,
+) -> Option<(f32, f32, f32)> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = vector_cross_product(ray_direction, &edge2);
+    let determinant = vector_dot_product(&edge1, &p);
+    if determinant.abs() < 1e-8 {
+        // Ray is parallel to the triangle
+        return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let s = ray_origin - v0;
+    let u = vector_dot_product(&s, &p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = vector_cross_product(&s, &edge1);
+    let v = vector_dot_product(ray_direction, &q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = vector_dot_product(&edge2, &q) * inverse_determinant;
+    if t < 0.0 {
+        return None;
+    }
+
+    Some((t, u, v))
+}
+
+// Closest point to `point` on the triangle (v0, v1, v2), from Ericson's Real-Time Collision Detection
+pub fn closest_point_on_triangle(
+    point: &Vector3D,
+    v0: &Vector3D,
+    v1: &Vector3D,
+    v2: &Vector3D,
+) -> Vector3D {
+    let ab = v1 - v0;
+    let ac = v2 - v0;
+    let ap = point - v0;
+    let d1 = vector_dot_product(&ab, &ap);
+    let d2 = vector_dot_product(&ac, &ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return *v0;
+    }
+
+    let bp = point - v1;
+    let d3 = vector_dot_product(&ab, &bp);
+    let d4 = vector_dot_product(&ac, &bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return *v1;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return v0 + ab * v;
+    }
+
+    let cp = point - v2;
+    let d5 = vector_dot_product(&ab, &cp);
+    let d6 = vector_dot_product(&ac, &cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return *v2;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return v0 + ac * w;
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return v1 + (v2 - v1) * w;
+    }
+
+    let denominator = 1.0 / (va + vb + vc);
+    let v = vb * denominator;
+    let w = vc * denominator;
+    v0 + ab * v + ac * w
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_helpers::vector;
+    use proptest::prelude::*;
+
+    #[test]
+    fn interpolate_ascending() {
+        let result = interpolate(0.0, 0.0, 10.0, 5.0);
+        assert_eq!(result, vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+    }
+
+    #[test]
+    fn interpolate_descending() {
+        let result = interpolate(10.0, 5.0, 0.0, 0.0);
+        assert_eq!(result, vec![10.0, 8.0, 6.0, 4.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn interpolate_flat() {
+        assert_eq!(interpolate(3.0, 7.0, 9.0, 7.0), vec![3.0]);
+    }
+
+    #[test]
+    fn cross_product_of_axes() {
+        let x = Vector3D::from_coords(1.0, 0.0, 0.0);
+        let y = Vector3D::from_coords(0.0, 1.0, 0.0);
+        assert_eq!(
+            vector_cross_product(&x, &y),
+            Vector3D::from_coords(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn intersect_plane_halfway() {
+        let point = intersect_plane(
+            &Vector3D::from_coords(0.0, 0.0, 1.0),
+            &Vector3D::from_coords(0.0, 0.0, 2.0),
+            &Vector3D::from_coords(1.0, 1.0, 0.0),
+            &Vector3D::from_coords(1.0, 1.0, 4.0),
+        );
+        assert!(point.approx_eq(&Vector3D::from_coords(1.0, 1.0, 1.0), 1e-6));
+    }
+
+    proptest! {
+        #[test]
+        fn interpolate_covers_every_row(
+            x_start in -500.0f32..500.0,
+            y_start in -500i32..500,
+            x_end in -500.0f32..500.0,
+            y_end in -500i32..500,
+        ) {
+            prop_assume!(y_start != y_end);
+            let result = interpolate(x_start, y_start as f32, x_end, y_end as f32);
+
+            prop_assert_eq!(result.len(), (y_end - y_start).unsigned_abs() as usize + 1);
+            prop_assert_eq!(result[0], x_start);
+            prop_assert!((result[result.len() - 1] - x_end).abs() < 0.01);
+        }
+
+        #[test]
+        fn cross_product_is_orthogonal(a in vector(), b in vector()) {
+            let cross = vector_cross_product(&a, &b);
+            let tolerance = 1e-4 * vector_len(&a) * vector_len(&b) * (vector_len(&a) + vector_len(&b));
+
+            prop_assert!(vector_dot_product(&cross, &a).abs() <= tolerance);
+            prop_assert!(vector_dot_product(&cross, &b).abs() <= tolerance);
+        }
+
+        #[test]
+        fn cross_product_is_anticommutative(a in vector(), b in vector()) {
+            let cross = vector_cross_product(&a, &b);
+            prop_assert!(cross.approx_eq(&-vector_cross_product(&b, &a), 1e-3));
+        }
+
+        #[test]
+        fn intersect_plane_finds_crossing(
+            plane_position in vector(),
+            plane_normal in vector(),
+            offset in vector(),
+            direction in vector(),
+            t in 0.05f32..0.95,
+        ) {
+            let normal = plane_normal.from_normalise();
+            // Only lines that clearly cross the plane have a well defined intersection
+            prop_assume!(vector_len(&plane_normal) > 0.1);
+            prop_assume!(vector_dot_product(&normal, &direction).abs() > 1.0);
+
+            // Build a line that crosses the plane at a known point
+            let crossing = plane_position + offset - normal * vector_dot_product(&normal, &offset);
+            let line_start = crossing - direction * t;
+            let line_end = crossing + direction * (1.0 - t);
+
+            let point = intersect_plane(&plane_position, &plane_normal, &line_start, &line_end);
+            prop_assert!(point.approx_eq(&crossing, 0.05));
+        }
+
+        #[test]
+        fn ray_triangle_intersection_finds_point_inside(
+            v0 in vector(),
+            v1 in vector(),
+            v2 in vector(),
+            origin in vector(),
+            u in 0.01f32..0.98,
+            v_fraction in 0.01f32..0.99,
+        ) {
+            let (edge1, edge2) = (v1 - v0, v2 - v0);
+            let normal = vector_cross_product(&edge1, &edge2);
+            // Slivers lose too much precision in f32
+            prop_assume!(vector_len(&normal) > 0.05 * vector_len(&edge1) * vector_len(&edge2));
+
+            // Aim the ray at a known point inside the triangle
+            let v = (1.0 - u) * v_fraction;
+            let target = v0 + (v1 - v0) * u + (v2 - v0) * v;
+            let to_target = target - origin;
+            prop_assume!(vector_len(&to_target) > 1.0);
+            let direction = to_target.from_normalise();
+            prop_assume!(vector_dot_product(&normal.from_normalise(), &direction).abs() > 0.1);
+
+            let (t, hit_u, hit_v) =
+                ray_triangle_intersection(&origin, &direction, &v0, &v1, &v2).unwrap();
+            prop_assert!((origin + direction * t).approx_eq(&target, 0.05));
+            prop_assert!((hit_u - u).abs() < 2e-3);
+            prop_assert!((hit_v - v).abs() < 2e-3);
+
+            // The plane is crossed behind a ray pointing the other way
+            prop_assert!(ray_triangle_intersection(&origin, &-direction, &v0, &v1, &v2).is_none());
+        }
+
+        #[test]
+        fn closest_point_on_triangle_beats_every_sample(
+            v0 in vector(),
+            v1 in vector(),
+            v2 in vector(),
+            point in vector(),
+        ) {
+            // Slivers lose too much precision in f32
+            let (edge1, edge2) = (v1 - v0, v2 - v0);
+            let normal = vector_cross_product(&edge1, &edge2);
+            prop_assume!(vector_len(&normal) > 0.05 * vector_len(&edge1) * vector_len(&edge2));
+
+            let closest = closest_point_on_triangle(&point, &v0, &v1, &v2);
+            let distance = vector_len(&(closest - point));
+
+            for i in 0..=10 {
+                for j in 0..=10 - i {
+                    let (u, v) = (i as f32 / 10.0, j as f32 / 10.0);
+                    let sample = v0 + (v1 - v0) * u + (v2 - v0) * v;
+                    prop_assert!(distance <= vector_len(&(sample - point)) + 1e-2);
+                }
+            }
+
+            // Points on the triangle are their own closest point
+            let inside = v0 + (v1 - v0) * 0.25 + (v2 - v0) * 0.25;
+            prop_assert!(closest_point_on_triangle(&inside, &v0, &v1, &v2).approx_eq(&inside, 1e-2));
+        }
+    }
 }
@@ -1,6 +1,9 @@
+use std::str::FromStr;
+
 use super::{
     math::{vector_cross_product, vector_dot_product},
     vector_3d::Vector3D,
+    vector_4d::Vector4D,
 };
 
 pub struct Matrix4X4 {
@@ -74,6 +77,40 @@ impl Matrix4X4 {
         matrix
     }
 
+    pub fn from_scale(x: f32, y: f32, z: f32) -> Self {
+        let mut matrix = Self::new();
+
+        matrix.content[0][0] = x;
+        matrix.content[1][1] = y;
+        matrix.content[2][2] = z;
+        matrix.content[3][3] = 1.0;
+
+        matrix
+    }
+
+    // Rotation around an arbitrary axis (Rodrigues), turns the same way as `from_rotation_x`
+    pub fn from_axis_angle(axis: &Vector3D, angle_rads: f32) -> Self {
+        let mut matrix = Self::new();
+        let axis = axis.from_normalise();
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        let cos = angle_rads.cos();
+        let sin = angle_rads.sin();
+        let t = 1.0 - cos;
+
+        matrix.content[0][0] = t * x * x + cos;
+        matrix.content[0][1] = t * x * y + sin * z;
+        matrix.content[0][2] = t * x * z - sin * y;
+        matrix.content[1][0] = t * x * y - sin * z;
+        matrix.content[1][1] = t * y * y + cos;
+        matrix.content[1][2] = t * y * z + sin * x;
+        matrix.content[2][0] = t * x * z + sin * y;
+        matrix.content[2][1] = t * y * z - sin * x;
+        matrix.content[2][2] = t * z * z + cos;
+        matrix.content[3][3] = 1.0;
+
+        matrix
+    }
+
     pub fn from_projection(
         fov_degrees: f32,
         aspect_ratio: f32,
@@ -93,9 +130,28 @@ impl Matrix4X4 {
         matrix
     }
 
+    // Parallel projection showing `height` world units vertically. Depth maps to
+    // 0.0 at the near plane and 1.0 at the far plane, like `from_projection`.
+    pub fn from_orthographic(
+        height: f32,
+        aspect_ratio: f32,
+        near_plane: f32,
+        far_plane: f32,
+    ) -> Self {
+        let mut matrix = Matrix4X4::new();
+
+        matrix.content[0][0] = aspect_ratio * 2.0 / height;
+        matrix.content[1][1] = 2.0 / height;
+        matrix.content[2][2] = 1.0 / (far_plane - near_plane);
+        matrix.content[3][2] = -near_plane / (far_plane - near_plane);
+        matrix.content[3][3] = 1.0;
+
+        matrix
+    }
+
     pub fn from_point_at(position: &Vector3D, target: &Vector3D, up: &Vector3D) -> Self {
         let new_forward = (target - position).from_normalise();
-        let new_up = (up - &(&new_forward * vector_dot_product(up, &new_forward))).from_normalise();
+        let new_up = (up - new_forward * vector_dot_product(up, &new_forward)).from_normalise();
         let new_right = vector_cross_product(&new_up, &new_forward);
 
         let mut matrix = Matrix4X4::new();
@@ -146,6 +202,136 @@ impl Matrix4X4 {
 
         matrix
     }
+
+    pub fn transpose(&self) -> Self {
+        let mut matrix = Matrix4X4::new();
+
+        for row in 0..4 {
+            for col in 0..4 {
+                matrix.content[col][row] = self.content[row][col];
+            }
+        }
+
+        matrix
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let (s, c) = self.sub_determinants();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    // Full inverse through the adjugate, `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let (s, c) = self.sub_determinants();
+        let determinant =
+            s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if !determinant.is_normal() {
+            return None;
+        }
+
+        let a = &self.content;
+        let mut matrix = Matrix4X4::new();
+        matrix.content = [
+            [
+                a[1][1] * c[5] - a[1][2] * c[4] + a[1][3] * c[3],
+                -a[0][1] * c[5] + a[0][2] * c[4] - a[0][3] * c[3],
+                a[3][1] * s[5] - a[3][2] * s[4] + a[3][3] * s[3],
+                -a[2][1] * s[5] + a[2][2] * s[4] - a[2][3] * s[3],
+            ],
+            [
+                -a[1][0] * c[5] + a[1][2] * c[2] - a[1][3] * c[1],
+                a[0][0] * c[5] - a[0][2] * c[2] + a[0][3] * c[1],
+                -a[3][0] * s[5] + a[3][2] * s[2] - a[3][3] * s[1],
+                a[2][0] * s[5] - a[2][2] * s[2] + a[2][3] * s[1],
+            ],
+            [
+                a[1][0] * c[4] - a[1][1] * c[2] + a[1][3] * c[0],
+                -a[0][0] * c[4] + a[0][1] * c[2] - a[0][3] * c[0],
+                a[3][0] * s[4] - a[3][1] * s[2] + a[3][3] * s[0],
+                -a[2][0] * s[4] + a[2][1] * s[2] - a[2][3] * s[0],
+            ],
+            [
+                -a[1][0] * c[3] + a[1][1] * c[1] - a[1][2] * c[0],
+                a[0][0] * c[3] - a[0][1] * c[1] + a[0][2] * c[0],
+                -a[3][0] * s[3] + a[3][1] * s[1] - a[3][2] * s[0],
+                a[2][0] * s[3] - a[2][1] * s[1] + a[2][2] * s[0],
+            ],
+        ];
+
+        for row in matrix.content.iter_mut() {
+            for value in row.iter_mut() {
+                *value /= determinant;
+            }
+        }
+
+        Some(matrix)
+    }
+
+    // Matrix for transforming normals: the inverse-transpose of the upper 3x3, so
+    // normals stay perpendicular to surfaces under non-uniform scaling. `None` when
+    // the matrix flattens space.
+    pub fn normal_matrix(&self) -> Option<Self> {
+        let mut linear = Self::from_identity();
+        for row in 0..3 {
+            linear.content[row][..3].copy_from_slice(&self.content[row][..3]);
+        }
+
+        Some(linear.inverse()?.transpose())
+    }
+
+    // 2x2 determinants of the top two rows (s) and the bottom two rows (c),
+    // shared by the determinant and the inverse
+    fn sub_determinants(&self) -> ([f32; 6], [f32; 6]) {
+        let a = &self.content;
+        let s = [
+            a[0][0] * a[1][1] - a[1][0] * a[0][1],
+            a[0][0] * a[1][2] - a[1][0] * a[0][2],
+            a[0][0] * a[1][3] - a[1][0] * a[0][3],
+            a[0][1] * a[1][2] - a[1][1] * a[0][2],
+            a[0][1] * a[1][3] - a[1][1] * a[0][3],
+            a[0][2] * a[1][3] - a[1][2] * a[0][3],
+        ];
+        let c = [
+            a[2][0] * a[3][1] - a[3][0] * a[2][1],
+            a[2][0] * a[3][2] - a[3][0] * a[2][2],
+            a[2][0] * a[3][3] - a[3][0] * a[2][3],
+            a[2][1] * a[3][2] - a[3][1] * a[2][2],
+            a[2][1] * a[3][3] - a[3][1] * a[2][3],
+            a[2][2] * a[3][3] - a[3][2] * a[2][3],
+        ];
+        (s, c)
+    }
+}
+
+impl Clone for Matrix4X4 {
+    fn clone(&self) -> Self {
+        Self {
+            content: self.content,
+        }
+    }
+}
+
+impl FromStr for Matrix4X4 {
+    type Err = String;
+
+    // Parses 16 values in row-major order, separated by commas and/or whitespace
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        if parts.len() != 16 {
+            return Err(format!("Expected 16 values, found {}", parts.len()));
+        }
+
+        let mut matrix = Matrix4X4::new();
+        for (i, part) in parts.iter().enumerate() {
+            matrix.content[i / 4][i % 4] = part
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid matrix value: {}", part))?;
+        }
+        Ok(matrix)
+    }
 }
 
 impl std::ops::Mul for &Matrix4X4 {
@@ -167,29 +353,175 @@ impl std::ops::Mul for &Matrix4X4 {
     }
 }
 
+// Transforms a point, the implicit `w` of 1.0 means translation is applied
 impl std::ops::Mul<&Vector3D> for &Matrix4X4 {
     type Output = Vector3D;
 
     fn mul(self, vector: &Vector3D) -> Self::Output {
-        let mut new_vector = Vector3D::new();
-
-        new_vector.x = vector.x * self.content[0][0]
-            + vector.y * self.content[1][0]
-            + vector.z * self.content[2][0]
-            + vector.w * self.content[3][0];
-        new_vector.y = vector.x * self.content[0][1]
-            + vector.y * self.content[1][1]
-            + vector.z * self.content[2][1]
-            + vector.w * self.content[3][1];
-        new_vector.z = vector.x * self.content[0][2]
-            + vector.y * self.content[1][2]
-            + vector.z * self.content[2][2]
-            + vector.w * self.content[3][2];
-        new_vector.w = vector.x * self.content[0][3]
-            + vector.y * self.content[1][3]
-            + vector.z * self.content[2][3]
-            + vector.w * self.content[3][3];
-
-        new_vector
+        (self * &Vector4D::from_point(vector)).xyz()
+    }
+}
+
+impl std::ops::Mul<&Vector4D> for &Matrix4X4 {
+    type Output = Vector4D;
+
+    fn mul(self, vector: &Vector4D) -> Self::Output {
+        Vector4D::from_coords(
+            vector.x * self.content[0][0]
+                + vector.y * self.content[1][0]
+                + vector.z * self.content[2][0]
+                + vector.w * self.content[3][0],
+            vector.x * self.content[0][1]
+                + vector.y * self.content[1][1]
+                + vector.z * self.content[2][1]
+                + vector.w * self.content[3][1],
+            vector.x * self.content[0][2]
+                + vector.y * self.content[1][2]
+                + vector.z * self.content[2][2]
+                + vector.w * self.content[3][2],
+            vector.x * self.content[0][3]
+                + vector.y * self.content[1][3]
+                + vector.z * self.content[2][3]
+                + vector.w * self.content[3][3],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::math::{vector_dot_product, vector_len};
+    use crate::core::test_helpers::vector;
+    use proptest::prelude::*;
+
+    fn is_identity(matrix: &Matrix4X4, epsilon: f32) -> bool {
+        let identity = Matrix4X4::from_identity();
+        (0..4).all(|row| {
+            (0..4)
+                .all(|col| (matrix.content[row][col] - identity.content[row][col]).abs() <= epsilon)
+        })
+    }
+
+    #[test]
+    fn point_at_looks_along_z() {
+        let matrix = Matrix4X4::from_point_at(
+            &Vector3D::from_coords(1.0, 2.0, 3.0),
+            &Vector3D::from_coords(1.0, 2.0, 4.0),
+            &Vector3D::from_coords(0.0, 1.0, 0.0),
+        );
+        let forward = &matrix * &Vector3D::from_coords(0.0, 0.0, 1.0);
+        assert!(forward.approx_eq(&Vector3D::from_coords(1.0, 2.0, 4.0), 1e-6));
+    }
+
+    #[test]
+    fn translation_moves_points() {
+        let matrix = Matrix4X4::from_translation(1.0, -2.0, 3.0);
+        let point = &matrix * &Vector3D::from_coords(1.0, 1.0, 1.0);
+        assert_eq!(point, Vector3D::from_coords(2.0, -1.0, 4.0));
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert!(Matrix4X4::new().inverse().is_none());
+        assert!(Matrix4X4::from_scale(1.0, 0.0, 1.0).inverse().is_none());
+    }
+
+    #[test]
+    fn orthographic_maps_view_box_to_unit_cube() {
+        let matrix = Matrix4X4::from_orthographic(4.0, 0.5, 1.0, 11.0);
+        let near_corner = &matrix * &Vector4D::from_coords(4.0, 2.0, 1.0, 1.0);
+        let far_corner = &matrix * &Vector4D::from_coords(-4.0, -2.0, 11.0, 1.0);
+        assert!(near_corner.approx_eq(&Vector4D::from_coords(1.0, 1.0, 0.0, 1.0), 1e-6));
+        assert!(far_corner.approx_eq(&Vector4D::from_coords(-1.0, -1.0, 1.0, 1.0), 1e-6));
+    }
+
+    #[test]
+    fn scale_determinant() {
+        assert_eq!(Matrix4X4::from_scale(2.0, 3.0, 4.0).determinant(), 24.0);
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_scaling() {
+        // The plane x + y = 1, stretched along x
+        let world = &(&Matrix4X4::from_scale(4.0, 1.0, 0.5) * &Matrix4X4::from_rotation_y(0.7))
+            * &Matrix4X4::from_translation(1.0, 2.0, 3.0);
+        let normal_matrix = world.normal_matrix().unwrap();
+        let normal = (&normal_matrix
+            * &Vector4D::from_direction(&Vector3D::from_coords(1.0, 1.0, 0.0)))
+            .xyz();
+
+        let a = &world * &Vector3D::from_coords(1.0, 0.0, 0.0);
+        let b = &world * &Vector3D::from_coords(0.0, 1.0, 0.0);
+        let c = &world * &Vector3D::from_coords(0.0, 1.0, 5.0);
+        assert!(vector_dot_product(&normal, &(b - a)).abs() < 1e-4);
+        assert!(vector_dot_product(&normal, &(c - b)).abs() < 1e-4);
+        // Transforming the normal like a direction would tilt it
+        let naive =
+            (&world * &Vector4D::from_direction(&Vector3D::from_coords(1.0, 1.0, 0.0))).xyz();
+        assert!(vector_dot_product(&naive, &(b - a)).abs() > 1.0);
+
+        assert!(Matrix4X4::from_scale(1.0, 0.0, 1.0)
+            .normal_matrix()
+            .is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn quick_inverse_round_trips(position in vector(), target in vector(), point in vector()) {
+            let forward = target - position;
+            prop_assume!(vector_len(&forward) > 0.1);
+            // Looking straight up or down leaves the up vector undefined
+            prop_assume!(forward.y.abs() < 0.99 * vector_len(&forward));
+
+            let matrix =
+                Matrix4X4::from_point_at(&position, &target, &Vector3D::from_coords(0.0, 1.0, 0.0));
+            let inverse = matrix.quick_inverse();
+
+            prop_assert!(is_identity(&(&matrix * &inverse), 1e-4));
+            let round_trip = &inverse * &(&matrix * &point);
+            prop_assert!(round_trip.approx_eq(&point, 1e-2));
+        }
+
+        #[test]
+        fn inverse_round_trips(
+            scale in (0.1f32..10.0, 0.1f32..10.0, 0.1f32..10.0),
+            axis in vector(),
+            angle in -6.0f32..6.0,
+            translation in vector(),
+        ) {
+            prop_assume!(vector_len(&axis) > 0.1);
+            let matrix = &(&Matrix4X4::from_scale(scale.0, scale.1, scale.2)
+                * &Matrix4X4::from_axis_angle(&axis, angle))
+                * &Matrix4X4::from_translation(translation.x, translation.y, translation.z);
+
+            let inverse = matrix.inverse().unwrap();
+            prop_assert!(is_identity(&(&matrix * &inverse), 1e-3));
+            prop_assert!(is_identity(&(&inverse * &matrix), 1e-3));
+        }
+
+        #[test]
+        fn projection_inverse_round_trips(fov in 30.0f32..120.0, aspect in 0.3f32..3.0) {
+            let matrix = Matrix4X4::from_projection(fov, aspect, 0.1, 1000.0);
+            let inverse = matrix.inverse().unwrap();
+            prop_assert!(is_identity(&(&matrix * &inverse), 1e-3));
+        }
+
+        #[test]
+        fn transpose_is_an_involution(values in prop::array::uniform16(-100.0f32..100.0)) {
+            let mut matrix = Matrix4X4::new();
+            for (i, value) in values.iter().enumerate() {
+                matrix.content[i / 4][i % 4] = *value;
+            }
+            prop_assert_eq!(matrix.transpose().transpose().content, matrix.content);
+
+            // Rounding error scales with the largest possible determinant (Hadamard's bound)
+            let bound: f32 = matrix
+                .content
+                .iter()
+                .map(|row| row.iter().map(|value| value * value).sum::<f32>().sqrt())
+                .product();
+            let difference = matrix.transpose().determinant() - matrix.determinant();
+            prop_assert!(difference.abs() <= 1e-4 * bound);
+        }
     }
 }
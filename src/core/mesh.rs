@@ -1,14 +1,270 @@
-use super::{files::load_from_obj_file, triangle::Triangle, vector_3d::Vector3D};
+use std::collections::VecDeque;
+
+use super::{
+    files::load_from_obj_file,
+    indexed_mesh::{face_has_directed_edge, IndexedMesh, VertexWelder},
+    math::vector_len,
+    matrix_4x4::Matrix4X4,
+    simplify::simplify_mesh,
+    subdivision::{catmull_clark_subdivide, loop_subdivide},
+    triangle::Triangle,
+    validation::MIN_TRIANGLE_AREA,
+    vector_3d::Vector3D,
+    vector_4d::Vector4D,
+};
 
 pub struct Mesh {
     pub triangles: Vec<Triangle>,
+    // Original polygon faces, only filled when the mesh was loaded with faces
+    // of more than 3 vertices. `triangles` always holds the triangulated faces.
+    pub polygons: Vec<Vec<Vector3D>>,
 }
 
 impl Mesh {
-    pub fn from_file(filename: &str) -> Mesh {
-        let mut mesh = Mesh { triangles: vec![] };
-        load_from_obj_file(filename, &mut mesh.triangles);
-        mesh
+    // Meshes without faces are rejected, they have nothing to draw or to put a
+    // bounding sphere around
+    pub fn from_file(filename: &str) -> Result<Mesh, String> {
+        let mut mesh = Mesh {
+            triangles: vec![],
+            polygons: vec![],
+        };
+        load_from_obj_file(filename, &mut mesh.triangles, &mut mesh.polygons)?;
+        if mesh.triangles.is_empty() {
+            return Err(format!("{} has no faces", filename));
+        }
+        Ok(mesh)
+    }
+
+    // Returns the minimum and maximum corner of the axis aligned bounding box
+    pub fn bounds(&self) -> (Vector3D, Vector3D) {
+        let mut min = Vector3D::from_coords(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+        let mut max =
+            Vector3D::from_coords(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+
+        for triangle in &self.triangles {
+            for vector in &triangle.vectors {
+                min.x = min.x.min(vector.x);
+                min.y = min.y.min(vector.y);
+                min.z = min.z.min(vector.z);
+                max.x = max.x.max(vector.x);
+                max.y = max.y.max(vector.y);
+                max.z = max.z.max(vector.z);
+            }
+        }
+
+        (min, max)
+    }
+
+    // Moves the center of the bounding box to the origin
+    pub fn recenter(&mut self) {
+        if self.triangles.is_empty() {
+            return;
+        }
+
+        let (min, max) = self.bounds();
+        let center = (min + max) * 0.5;
+        self.for_each_vertex_mut(|vector| {
+            vector.x -= center.x;
+            vector.y -= center.y;
+            vector.z -= center.z;
+        });
+    }
+
+    // Scales the mesh uniformly so its largest dimension is exactly 1 unit
+    pub fn scale_to_unit(&mut self) {
+        let (min, max) = self.bounds();
+        let size = max - min;
+        let largest = size.x.max(size.y).max(size.z);
+        if !largest.is_finite() || largest <= 0.0 {
+            return;
+        }
+
+        let scale = 1.0 / largest;
+        self.for_each_vertex_mut(|vector| {
+            vector.x *= scale;
+            vector.y *= scale;
+            vector.z *= scale;
+        });
+    }
+
+    // Rotates a Z-up model (most CAD exports) so that it is Y-up like the engine
+    pub fn z_up_to_y_up(&mut self) {
+        self.for_each_vertex_mut(|vector| {
+            let y = vector.y;
+            vector.y = vector.z;
+            vector.z = -y;
+        });
+    }
+
+    // Reverses the vertex order of every triangle, which flips the facing used for culling
+    pub fn flip_winding(&mut self) {
+        for triangle in &mut self.triangles {
+            triangle.vectors.swap(1, 2);
+        }
+        for polygon in &mut self.polygons {
+            polygon.reverse();
+        }
+    }
+
+    // Bakes a transformation into the vertices of the mesh. A projective matrix
+    // can send vertices to infinity, then the mesh is left unchanged.
+    pub fn apply_transform(&mut self, matrix: &Matrix4X4) -> Result<(), String> {
+        for triangle in &self.triangles {
+            for vector in &triangle.vectors {
+                let w = (matrix * &Vector4D::from_point(vector)).w;
+                if w.abs() <= f32::EPSILON {
+                    return Err(format!("Transform moves vertex {} to infinity", vector));
+                }
+            }
+        }
+
+        self.for_each_vertex_mut(|vector| {
+            *vector = (matrix * &Vector4D::from_point(vector)).perspective_divide();
+        });
+        Ok(())
+    }
+
+    // Snaps vertices that are closer than `weld_distance` onto the same position,
+    // in the polygon faces as well
+    pub fn weld_vertices(&mut self, weld_distance: f32) {
+        let mut welder = VertexWelder::new(weld_distance);
+        self.for_each_vertex_mut(|vector| {
+            let index = welder.index_of(vector);
+            *vector = welder.vertices[index];
+        });
+    }
+
+    // Drops triangles without area, returns how many were removed. The polygon
+    // faces no longer match the triangles then, so they are dropped too.
+    pub fn remove_degenerate_triangles(&mut self) -> usize {
+        let count = self.triangles.len();
+        self.triangles
+            .retain(|triangle| triangle.area() >= MIN_TRIANGLE_AREA);
+        let removed = count - self.triangles.len();
+        if removed > 0 {
+            self.polygons.clear();
+        }
+        removed
+    }
+
+    // Flips triangles so that all connected triangles share the winding order of the
+    // first triangle in their patch. Returns how many triangles were flipped.
+    pub fn unify_orientation(&mut self, weld_distance: f32) -> usize {
+        let mut indexed = IndexedMesh::from_mesh(self, weld_distance);
+        let edge_faces = indexed.edge_faces();
+        let mut visited = vec![false; indexed.faces.len()];
+        let mut flipped = vec![false; indexed.faces.len()];
+
+        for start in 0..indexed.faces.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+
+            let mut queue = VecDeque::from([start]);
+            while let Some(face_index) = queue.pop_front() {
+                let face = indexed.faces[face_index];
+                for i in 0..3 {
+                    let a = face[i];
+                    let b = face[(i + 1) % 3];
+                    let Some(neighbours) = edge_faces.get(&(a.min(b), a.max(b))) else {
+                        continue;
+                    };
+                    // Orientation is undefined across non-manifold edges
+                    if neighbours.len() != 2 {
+                        continue;
+                    }
+
+                    for &neighbour in neighbours {
+                        if visited[neighbour] {
+                            continue;
+                        }
+                        visited[neighbour] = true;
+
+                        if face_has_directed_edge(&indexed.faces[neighbour], a, b) {
+                            indexed.faces[neighbour].swap(1, 2);
+                            flipped[neighbour] = true;
+                        }
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+
+        let mut count = 0;
+        for (triangle, is_flipped) in self.triangles.iter_mut().zip(flipped) {
+            if is_flipped {
+                triangle.vectors.swap(1, 2);
+                count += 1;
+            }
+        }
+        // Which faces the flipped triangles came from isn't known, so subdivision
+        // has to use the triangles from now on
+        if count > 0 {
+            self.polygons.clear();
+        }
+        count
+    }
+
+    // Reduces the triangle count, see `simplify_mesh`
+    pub fn simplify(&self, target_triangles: usize, max_error: f32) -> Mesh {
+        simplify_mesh(self, target_triangles, max_error)
+    }
+
+    // One level of subdivision: Catmull-Clark when polygon faces are available,
+    // Loop subdivision otherwise
+    pub fn subdivide(&self) -> Mesh {
+        if self.polygons.is_empty() {
+            loop_subdivide(self)
+        } else {
+            catmull_clark_subdivide(self)
+        }
+    }
+
+    // Center and radius of a sphere enclosing the whole mesh
+    pub fn bounding_sphere(&self) -> (Vector3D, f32) {
+        let (min, max) = self.bounds();
+        let center = (min + max) * 0.5;
+
+        let mut radius: f32 = 0.0;
+        for triangle in &self.triangles {
+            for vector in &triangle.vectors {
+                radius = radius.max(vector_len(&(vector - center)));
+            }
+        }
+
+        (center, radius)
+    }
+
+    // Center of the surface, every triangle counts as much as its area
+    pub fn centroid(&self) -> Vector3D {
+        let mut centroid = Vector3D::new();
+        let mut total_area = 0.0;
+        for triangle in &self.triangles {
+            let area = triangle.area();
+            let [a, b, c] = triangle.vectors;
+            centroid += (a + b + c) * (area / 3.0);
+            total_area += area;
+        }
+
+        if total_area > 0.0 {
+            centroid / total_area
+        } else {
+            self.bounding_sphere().0
+        }
+    }
+
+    fn for_each_vertex_mut(&mut self, mut f: impl FnMut(&mut Vector3D)) {
+        for triangle in &mut self.triangles {
+            for vector in &mut triangle.vectors {
+                f(vector);
+            }
+        }
+        for polygon in &mut self.polygons {
+            for vector in polygon {
+                f(vector);
+            }
+        }
     }
 
     pub fn from_cube() -> Mesh {
@@ -80,6 +336,7 @@ impl Mesh {
                     Vector3D::from_coords(1.0, 0.0, 0.0),
                 ]),
             ],
+            polygons: vec![],
         }
     }
 
@@ -123,6 +380,7 @@ impl Mesh {
                     Vector3D::from_coords(1.0, 1.0, 0.0), // Base top-right
                 ]),
             ],
+            polygons: vec![],
         }
     }
 
@@ -175,17 +433,166 @@ impl Mesh {
             let bottom2 =
                 Vector3D::from_coords(0.7 + 0.7 * angle2.cos(), 0.7 + 0.7 * angle2.sin(), 0.0);
 
-            triangles.push(Triangle::new_from_vectors([
-                top1.clone(),
-                top2.clone(),
-                bottom1.clone(),
-            ]));
-            triangles.push(Triangle::new_from_vectors([
-                top2.clone(),
-                bottom2.clone(),
-                bottom1.clone(),
-            ]));
+            triangles.push(Triangle::new_from_vectors([top1, top2, bottom1]));
+            triangles.push(Triangle::new_from_vectors([top2, bottom2, bottom1]));
+        }
+        Mesh {
+            triangles,
+            polygons: vec![],
+        }
+    }
+}
+
+impl Clone for Mesh {
+    fn clone(&self) -> Mesh {
+        Mesh {
+            triangles: self.triangles.clone(),
+            polygons: self.polygons.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::math::vector_cross_product;
+
+    fn face_normal(triangle: &Triangle) -> Vector3D {
+        let [a, b, c] = triangle.vectors;
+        vector_cross_product(&(b - a), &(c - a)).from_normalise()
+    }
+
+    // A unit square in the xy plane, as a polygon and as its triangle fan
+    fn square() -> Mesh {
+        let corners = vec![
+            Vector3D::from_coords(0.0, 0.0, 0.0),
+            Vector3D::from_coords(1.0, 0.0, 0.0),
+            Vector3D::from_coords(1.0, 1.0, 0.0),
+            Vector3D::from_coords(0.0, 1.0, 0.0),
+        ];
+        Mesh {
+            triangles: vec![
+                Triangle::from_vectors(&corners, [0, 1, 2]),
+                Triangle::from_vectors(&corners, [0, 2, 3]),
+            ],
+            polygons: vec![corners],
         }
-        Mesh { triangles }
+    }
+
+    #[test]
+    fn welding_moves_polygon_vertices_too() {
+        let mut mesh = square();
+        mesh.polygons[0][2].x += 1e-5;
+        mesh.weld_vertices(1e-3);
+
+        assert_eq!(mesh.polygons[0][2], mesh.triangles[0].vectors[2]);
+        assert_eq!(mesh.polygons[0][2], mesh.triangles[1].vectors[1]);
+    }
+
+    #[test]
+    fn repairs_that_change_triangles_drop_the_polygons() {
+        let mut mesh = square();
+        assert_eq!(mesh.remove_degenerate_triangles(), 0);
+        assert_eq!(mesh.unify_orientation(0.0), 0);
+        assert_eq!(mesh.polygons.len(), 1);
+
+        let mut degenerate = square();
+        let point = Vector3D::from_coords(2.0, 0.0, 0.0);
+        degenerate
+            .triangles
+            .push(Triangle::new_from_vectors([point; 3]));
+        assert_eq!(degenerate.remove_degenerate_triangles(), 1);
+        assert!(degenerate.polygons.is_empty());
+        // Loop subdivision of the repaired triangles instead of the old faces
+        assert_eq!(degenerate.subdivide().triangles.len(), 8);
+
+        let mut flipped = square();
+        flipped.triangles[1].vectors.swap(1, 2);
+        assert_eq!(flipped.unify_orientation(0.0), 1);
+        assert!(flipped.polygons.is_empty());
+    }
+
+    #[test]
+    fn loading_fails_without_faces() {
+        let error = Mesh::from_file("missing.obj").err().unwrap();
+        assert!(error.starts_with("Error reading missing.obj: "));
+
+        let filename = std::env::temp_dir().join("driedee_rs_no_faces.obj");
+        std::fs::write(&filename, "v 0 0 0\nv 1 0 0\nv 0 1 0\n").unwrap();
+        let filename = filename.to_str().unwrap();
+        let error = Mesh::from_file(filename).err();
+        assert_eq!(error, Some(format!("{} has no faces", filename)));
+
+        std::fs::write(filename, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        assert_eq!(Mesh::from_file(filename).unwrap().triangles.len(), 1);
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn recenter_and_scale_to_unit() {
+        let mut mesh = square();
+        mesh.apply_transform(&Matrix4X4::from_scale(4.0, 2.0, 1.0))
+            .unwrap();
+        mesh.recenter();
+        let (min, max) = mesh.bounds();
+        assert_eq!(min, Vector3D::from_coords(-2.0, -1.0, 0.0));
+        assert_eq!(max, Vector3D::from_coords(2.0, 1.0, 0.0));
+
+        mesh.scale_to_unit();
+        let (min, max) = mesh.bounds();
+        assert_eq!(min, Vector3D::from_coords(-0.5, -0.25, 0.0));
+        assert_eq!(max, Vector3D::from_coords(0.5, 0.25, 0.0));
+        assert_eq!(mesh.polygons[0][0], min);
+
+        // Nothing to scale in a single point
+        let point = Vector3D::from_coords(1.0, 2.0, 3.0);
+        let mut collapsed = Mesh {
+            triangles: vec![Triangle::new_from_vectors([point; 3])],
+            polygons: vec![],
+        };
+        collapsed.scale_to_unit();
+        assert_eq!(collapsed.triangles[0].vectors[0], point);
+    }
+
+    #[test]
+    fn z_up_becomes_y_up() {
+        let mut mesh = square();
+        mesh.z_up_to_y_up();
+
+        // The square lies flat and faces up instead of towards +z
+        assert_eq!(
+            mesh.triangles[0].vectors[2],
+            Vector3D::from_coords(1.0, 0.0, -1.0)
+        );
+        assert_eq!(mesh.polygons[0][3], Vector3D::from_coords(0.0, 0.0, -1.0));
+        let normal = face_normal(&mesh.triangles[0]);
+        assert!(normal.approx_eq(&Vector3D::from_coords(0.0, 1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn flip_winding_reverses_triangles_and_polygons() {
+        let mut mesh = square();
+        let normal = face_normal(&mesh.triangles[0]);
+        mesh.flip_winding();
+
+        assert!(face_normal(&mesh.triangles[0]).approx_eq(&-normal, 1e-6));
+        assert_eq!(mesh.polygons[0][0], Vector3D::from_coords(0.0, 1.0, 0.0));
+        assert_eq!(mesh.polygons[0][3], Vector3D::from_coords(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn transform_to_infinity_is_rejected() {
+        // w becomes 1 - x, which is 0 at the square's right edge
+        let mut projective = Matrix4X4::from_identity();
+        projective.content[0][3] = -1.0;
+
+        let mut mesh = square();
+        assert!(mesh.apply_transform(&projective).is_err());
+        assert_eq!(mesh.polygons[0], square().polygons[0]);
+
+        // Away from the edge the division is done
+        projective.content[0][3] = -0.5;
+        mesh.apply_transform(&projective).unwrap();
+        assert_eq!(mesh.polygons[0][1], Vector3D::from_coords(2.0, 0.0, 0.0));
     }
 }
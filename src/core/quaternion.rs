@@ -0,0 +1,375 @@
+use super::{
+    math::{vector_cross_product, vector_dot_product},
+    matrix_4x4::Matrix4X4,
+    vector_3d::Vector3D,
+};
+
+// Unit quaternion describing an orientation. Rotations follow the same
+// conventions as `Matrix4X4`, so `to_matrix` can be used in the pipeline directly.
+pub struct Quaternion {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Quaternion {
+    pub fn from_identity() -> Self {
+        Self {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    pub fn from_axis_angle(axis: &Vector3D, angle_rads: f32) -> Self {
+        let axis = axis.from_normalise();
+        let sin = (angle_rads * 0.5).sin();
+
+        Self {
+            w: (angle_rads * 0.5).cos(),
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+        }
+    }
+
+    // Same rotation as `from_rotation_z(roll) * from_rotation_x(pitch) * from_rotation_y(yaw)`,
+    // which is how the engine has always turned the camera
+    pub fn from_euler(pitch: f32, yaw: f32, roll: f32) -> Self {
+        let roll = Self::from_axis_angle(&Vector3D::from_coords(0.0, 0.0, 1.0), roll);
+        let pitch = Self::from_axis_angle(&Vector3D::from_coords(1.0, 0.0, 0.0), pitch);
+        // `from_rotation_y` turns the other way around the y axis
+        let yaw = Self::from_axis_angle(&Vector3D::from_coords(0.0, 1.0, 0.0), -yaw);
+
+        &(&roll * &pitch) * &yaw
+    }
+
+    // Returns (pitch, yaw, roll), the inverse of `from_euler`
+    pub fn to_euler(&self) -> (f32, f32, f32) {
+        let m = self.to_matrix().content;
+        let pitch = (-m[2][1]).clamp(-1.0, 1.0).asin();
+
+        // Looking straight up or down, yaw and roll turn around the same axis
+        if m[2][1].abs() > 0.9999 {
+            return (pitch, m[0][2].atan2(m[0][0]), 0.0);
+        }
+
+        (pitch, (-m[2][0]).atan2(m[2][2]), m[0][1].atan2(m[1][1]))
+    }
+
+    // Only the rotation part of the matrix is used, it must not be scaled
+    pub fn from_matrix(matrix: &Matrix4X4) -> Self {
+        let m = &matrix.content;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        // Take the square root of the largest term to stay numerically stable
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self {
+                w: 0.25 * s,
+                x: (m[1][2] - m[2][1]) / s,
+                y: (m[2][0] - m[0][2]) / s,
+                z: (m[0][1] - m[1][0]) / s,
+            }
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Self {
+                w: (m[1][2] - m[2][1]) / s,
+                x: 0.25 * s,
+                y: (m[0][1] + m[1][0]) / s,
+                z: (m[0][2] + m[2][0]) / s,
+            }
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Self {
+                w: (m[2][0] - m[0][2]) / s,
+                x: (m[0][1] + m[1][0]) / s,
+                y: 0.25 * s,
+                z: (m[1][2] + m[2][1]) / s,
+            }
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Self {
+                w: (m[0][1] - m[1][0]) / s,
+                x: (m[0][2] + m[2][0]) / s,
+                y: (m[1][2] + m[2][1]) / s,
+                z: 0.25 * s,
+            }
+        };
+
+        quaternion.normalise()
+    }
+
+    pub fn to_matrix(&self) -> Matrix4X4 {
+        let mut matrix = Matrix4X4::new();
+        let (w, x, y, z) = (self.w, self.x, self.y, self.z);
+
+        matrix.content[0][0] = 1.0 - 2.0 * (y * y + z * z);
+        matrix.content[0][1] = 2.0 * (x * y + w * z);
+        matrix.content[0][2] = 2.0 * (x * z - w * y);
+        matrix.content[1][0] = 2.0 * (x * y - w * z);
+        matrix.content[1][1] = 1.0 - 2.0 * (x * x + z * z);
+        matrix.content[1][2] = 2.0 * (y * z + w * x);
+        matrix.content[2][0] = 2.0 * (x * z + w * y);
+        matrix.content[2][1] = 2.0 * (y * z - w * x);
+        matrix.content[2][2] = 1.0 - 2.0 * (x * x + y * y);
+        matrix.content[3][3] = 1.0;
+
+        matrix
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalise(&self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            return Self::from_identity();
+        }
+
+        Self {
+            w: self.w / length,
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+        }
+    }
+
+    // The inverse rotation, for unit quaternions
+    pub fn conjugate(&self) -> Self {
+        Self {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f32 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn rotate_vector(&self, vector: &Vector3D) -> Vector3D {
+        let axis = Vector3D::from_coords(self.x, self.y, self.z);
+        let t = vector_cross_product(&axis, vector) * 2.0;
+
+        vector + t * self.w + vector_cross_product(&axis, &t)
+    }
+
+    // Spherical interpolation along the shortest arc, `t` goes from 0.0 (self) to 1.0 (other)
+    pub fn slerp(&self, other: &Quaternion, t: f32) -> Self {
+        let mut cos_theta = self.dot(other);
+
+        // q and -q are the same orientation, take the one that is closest
+        let mut other = other.clone();
+        if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            other = Self {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            };
+        }
+
+        // Nearly the same orientation, fall back to a linear blend
+        let (a, b) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+
+        Self {
+            w: self.w * a + other.w * b,
+            x: self.x * a + other.x * b,
+            y: self.y * a + other.y * b,
+            z: self.z * a + other.z * b,
+        }
+        .normalise()
+    }
+}
+
+impl Clone for Quaternion {
+    fn clone(&self) -> Self {
+        Self {
+            w: self.w,
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+}
+
+// `a * b` rotates by `a` first and then by `b`, just like multiplying matrices
+impl std::ops::Mul for &Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let first = Vector3D::from_coords(self.x, self.y, self.z);
+        let second = Vector3D::from_coords(rhs.x, rhs.y, rhs.z);
+        let cross = vector_cross_product(&second, &first);
+
+        Quaternion {
+            w: rhs.w * self.w - vector_dot_product(&second, &first),
+            x: rhs.w * first.x + self.w * second.x + cross.x,
+            y: rhs.w * first.y + self.w * second.y + cross.y,
+            z: rhs.w * first.z + self.w * second.z + cross.z,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{math::vector_len, test_helpers::vector};
+    use proptest::prelude::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    // Axis and angle of a random rotation
+    fn rotation() -> impl Strategy<Value = (Vector3D, f32)> {
+        (vector(), -PI..PI).prop_filter("axis is too short", |(axis, _)| vector_len(axis) > 0.1)
+    }
+
+    fn matrices_match(a: &Matrix4X4, b: &Matrix4X4, epsilon: f32) -> bool {
+        (0..4).all(|row| {
+            (0..4).all(|col| (a.content[row][col] - b.content[row][col]).abs() <= epsilon)
+        })
+    }
+
+    // q and -q are the same orientation
+    fn same_orientation(a: &Quaternion, b: &Quaternion) -> bool {
+        a.dot(b).abs() > 1.0 - 1e-5
+    }
+
+    #[test]
+    fn axis_angle_turns_a_quarter() {
+        let quaternion =
+            Quaternion::from_axis_angle(&Vector3D::from_coords(0.0, 0.0, 2.0), FRAC_PI_2);
+        let rotated = quaternion.rotate_vector(&Vector3D::from_coords(1.0, 0.0, 0.0));
+
+        // Same direction as `Matrix4X4::from_rotation_z`
+        let expected =
+            &Matrix4X4::from_rotation_z(FRAC_PI_2) * &Vector3D::from_coords(1.0, 0.0, 0.0);
+        assert!(rotated.approx_eq(&expected, 1e-6));
+        assert!(rotated.approx_eq(&Vector3D::from_coords(0.0, 1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn normalise_makes_unit_length() {
+        let quaternion = Quaternion {
+            w: 1.0,
+            x: 2.0,
+            y: -2.0,
+            z: 4.0,
+        }
+        .normalise();
+        assert!((quaternion.length() - 1.0).abs() < 1e-6);
+        assert!((quaternion.w - 0.2).abs() < 1e-6);
+
+        // There is no direction to keep, so it falls back to no rotation
+        let zero = Quaternion {
+            w: 0.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+        .normalise();
+        assert!(same_orientation(&zero, &Quaternion::from_identity()));
+    }
+
+    #[test]
+    fn euler_near_straight_down_keeps_the_direction() {
+        let quaternion = Quaternion::from_euler(FRAC_PI_2, 0.7, 0.0);
+        let (pitch, yaw, roll) = quaternion.to_euler();
+
+        assert!((pitch - FRAC_PI_2).abs() < 1e-2);
+        assert!(same_orientation(
+            &Quaternion::from_euler(pitch, yaw, roll),
+            &quaternion
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn axis_angle_keeps_the_axis_and_matches_the_matrix(
+            (axis, angle) in rotation(),
+            vector in vector(),
+        ) {
+            let quaternion = Quaternion::from_axis_angle(&axis, angle);
+
+            prop_assert!((quaternion.length() - 1.0).abs() < 1e-5);
+            prop_assert!(quaternion.rotate_vector(&axis).approx_eq(&axis, 1e-3));
+            let by_matrix = &quaternion.to_matrix() * &vector;
+            prop_assert!(quaternion.rotate_vector(&vector).approx_eq(&by_matrix, 1e-3));
+            prop_assert!((vector_len(&quaternion.rotate_vector(&vector)) - vector_len(&vector)).abs() < 1e-3);
+        }
+
+        #[test]
+        fn euler_matches_the_matrix_rotations(
+            pitch in -PI..PI,
+            yaw in -PI..PI,
+            roll in -PI..PI,
+        ) {
+            let expected = &(&Matrix4X4::from_rotation_z(roll) * &Matrix4X4::from_rotation_x(pitch))
+                * &Matrix4X4::from_rotation_y(yaw);
+            let matrix = Quaternion::from_euler(pitch, yaw, roll).to_matrix();
+            prop_assert!(matrices_match(&matrix, &expected, 1e-5));
+        }
+
+        #[test]
+        fn euler_round_trip(pitch in -1.5f32..1.5, yaw in -3.1f32..3.1, roll in -3.1f32..3.1) {
+            let (pitch_back, yaw_back, roll_back) = Quaternion::from_euler(pitch, yaw, roll).to_euler();
+
+            prop_assert!((pitch_back - pitch).abs() < 1e-3);
+            prop_assert!((yaw_back - yaw).abs() < 1e-3);
+            prop_assert!((roll_back - roll).abs() < 1e-3);
+        }
+
+        #[test]
+        fn matrix_round_trip((axis, angle) in rotation()) {
+            let quaternion = Quaternion::from_axis_angle(&axis, angle);
+            let back = Quaternion::from_matrix(&quaternion.to_matrix());
+            prop_assert!(same_orientation(&back, &quaternion));
+        }
+
+        #[test]
+        fn slerp_starts_ends_and_halves(
+            (axis, angle) in rotation(),
+            (other_axis, other_angle) in rotation(),
+        ) {
+            let from = Quaternion::from_axis_angle(&other_axis, other_angle);
+            let to = &Quaternion::from_axis_angle(&axis, angle) * &from;
+
+            prop_assert!(same_orientation(&from.slerp(&to, 0.0), &from));
+            prop_assert!(same_orientation(&from.slerp(&to, 1.0), &to));
+
+            // Halfway is half of the rotation from one to the other, along the shortest arc
+            let half = &Quaternion::from_axis_angle(&axis, angle * 0.5) * &from;
+            prop_assert!(same_orientation(&from.slerp(&to, 0.5), &half));
+        }
+
+        #[test]
+        fn multiplication_rotates_by_the_left_side_first(
+            (axis, angle) in rotation(),
+            (other_axis, other_angle) in rotation(),
+            vector in vector(),
+        ) {
+            let first = Quaternion::from_axis_angle(&axis, angle);
+            let second = Quaternion::from_axis_angle(&other_axis, other_angle);
+            let product = &first * &second;
+
+            let expected = second.rotate_vector(&first.rotate_vector(&vector));
+            prop_assert!(product.rotate_vector(&vector).approx_eq(&expected, 1e-2));
+            let matrix_product = &first.to_matrix() * &second.to_matrix();
+            prop_assert!(matrices_match(&product.to_matrix(), &matrix_product, 1e-5));
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use super::vector_3d::Vector3D;
+
+pub struct Ray {
+    pub origin: Vector3D,
+    // Always normalised, so distances along the ray are in world units
+    pub direction: Vector3D,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3D, direction: Vector3D) -> Ray {
+        Ray {
+            origin,
+            direction: direction.from_normalise(),
+        }
+    }
+
+    pub fn at(&self, distance: f32) -> Vector3D {
+        self.origin + self.direction * distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_are_in_world_units() {
+        let ray = Ray::new(
+            Vector3D::from_coords(1.0, 2.0, 3.0),
+            Vector3D::from_coords(0.0, 0.0, 10.0),
+        );
+
+        assert_eq!(ray.direction, Vector3D::from_coords(0.0, 0.0, 1.0));
+        assert_eq!(ray.at(2.5), Vector3D::from_coords(1.0, 2.0, 5.5));
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    ops::RangeInclusive,
+    str::SplitWhitespace,
+};
+
+use sdl2::{
+    event::{Event, WindowEvent},
+    keyboard::{Keycode, Mod, Scancode},
+    mouse::{MouseButton, MouseState, MouseWheelDirection},
+};
+
+// The values SDL_scancode.h defines below SDL_NUM_SCANCODES (512)
+const SCANCODE_VALUES: [RangeInclusive<i32>; 6] =
+    [0..=0, 4..=129, 133..=164, 176..=221, 224..=231, 257..=290];
+
+// Everything that happened during one frame of a recording
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordedFrame {
+    // Seconds since the previous frame
+    pub delta: f32,
+    pub events: Vec<Event>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Recording {
+    // Update steps per second, the replay has to use the same rate to end up in
+    // the same place
+    pub tick_rate: f32,
+    pub frames: Vec<RecordedFrame>,
+}
+
+// Writes input events and frame times to a file, one frame at a time so the
+// recording survives a crash. The file is plain text:
+//
+//   tick_rate 60
+//   frame 0.016
+//   key_down 119 26 false
+//   mouse_motion 640 360 -3 1
+//
+// Only events the viewer reacts to are recorded, timestamps and window ids are not.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn create(filename: &str, tick_rate: f32) -> Result<InputRecorder, String> {
+        let file = File::create(filename)
+            .map_err(|err| format!("Error creating {}: {}", filename, err))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "tick_rate {}", tick_rate)
+            .map_err(|err| format!("Error writing {}: {}", filename, err))?;
+        Ok(InputRecorder { writer })
+    }
+
+    pub fn record_frame(&mut self, delta: f32, events: &[Event]) -> Result<(), String> {
+        self.writer
+            .write_all(format_frame(delta, events).as_bytes())
+            .and_then(|_| self.writer.flush())
+            .map_err(|err| format!("Error writing recording: {}", err))
+    }
+}
+
+pub fn load_recording(filename: &str) -> Result<Recording, String> {
+    let recording = fs::read_to_string(filename)
+        .map_err(|err| format!("Error reading {}: {}", filename, err))?;
+    parse_recording(&recording).map_err(|err| format!("{}: {}", filename, err))
+}
+
+// Recordings without a tick rate are replayed at 60 updates per second
+pub fn parse_recording(recording: &str) -> Result<Recording, String> {
+    let mut tick_rate = 60.0;
+    let mut frames: Vec<RecordedFrame> = vec![];
+
+    for (line_number, line) in recording.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: String| format!("line {}: {}", line_number + 1, message);
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("tick_rate") => tick_rate = next_value(&mut fields).map_err(error)?,
+            Some("frame") => {
+                let delta = next_value(&mut fields).map_err(error)?;
+                frames.push(RecordedFrame {
+                    delta,
+                    events: vec![],
+                });
+            }
+            Some(kind) => {
+                let event = parse_event(kind, &mut fields).map_err(error)?;
+                let frame = frames
+                    .last_mut()
+                    .ok_or_else(|| error("event before the first frame".to_string()))?;
+                frame.events.push(event);
+            }
+            None => {}
+        }
+    }
+
+    Ok(Recording { tick_rate, frames })
+}
+
+pub fn format_frame(delta: f32, events: &[Event]) -> String {
+    // `{}` prints the shortest text that parses back to the same f32
+    let mut text = format!("frame {}\n", delta);
+    for event in events {
+        if let Some(line) = format_event(event) {
+            text.push_str(&line);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+fn format_event(event: &Event) -> Option<String> {
+    let key = |kind: &str, keycode: Option<Keycode>, scancode: Option<Scancode>, repeat: bool| {
+        format!(
+            "{} {} {} {}",
+            kind,
+            keycode.map_or(0, |keycode| keycode.into_i32()),
+            scancode.map_or(0, |scancode| scancode as i32),
+            repeat
+        )
+    };
+
+    match *event {
+        Event::KeyDown {
+            keycode,
+            scancode,
+            repeat,
+            ..
+        } => Some(key("key_down", keycode, scancode, repeat)),
+        Event::KeyUp {
+            keycode,
+            scancode,
+            repeat,
+            ..
+        } => Some(key("key_up", keycode, scancode, repeat)),
+        Event::MouseButtonDown {
+            mouse_btn,
+            clicks,
+            x,
+            y,
+            ..
+        } => Some(format!(
+            "mouse_down {} {} {} {}",
+            mouse_btn as u8, clicks, x, y
+        )),
+        Event::MouseButtonUp {
+            mouse_btn,
+            clicks,
+            x,
+            y,
+            ..
+        } => Some(format!(
+            "mouse_up {} {} {} {}",
+            mouse_btn as u8, clicks, x, y
+        )),
+        Event::MouseMotion {
+            x, y, xrel, yrel, ..
+        } => Some(format!("mouse_motion {} {} {} {}", x, y, xrel, yrel)),
+        Event::MouseWheel { x, y, .. } => Some(format!("mouse_wheel {} {}", x, y)),
+        Event::Window {
+            win_event: WindowEvent::Resized(size_x, size_y),
+            ..
+        } => Some(format!("resize {} {}", size_x, size_y)),
+        Event::Quit { .. } => Some("quit".to_string()),
+        _ => None,
+    }
+}
+
+fn parse_event(kind: &str, fields: &mut SplitWhitespace) -> Result<Event, String> {
+    let event = match kind {
+        "key_down" | "key_up" => {
+            let keycode = Keycode::from_i32(next_value(fields)?);
+            let scancode = parse_scancode(next_value(fields)?)?;
+            let repeat = next_value(fields)?;
+            if kind == "key_down" {
+                Event::KeyDown {
+                    timestamp: 0,
+                    window_id: 0,
+                    keycode,
+                    scancode,
+                    keymod: Mod::NOMOD,
+                    repeat,
+                }
+            } else {
+                Event::KeyUp {
+                    timestamp: 0,
+                    window_id: 0,
+                    keycode,
+                    scancode,
+                    keymod: Mod::NOMOD,
+                    repeat,
+                }
+            }
+        }
+        "mouse_down" | "mouse_up" => {
+            let mouse_btn = MouseButton::from_ll(next_value(fields)?);
+            let clicks = next_value(fields)?;
+            let x = next_value(fields)?;
+            let y = next_value(fields)?;
+            if kind == "mouse_down" {
+                Event::MouseButtonDown {
+                    timestamp: 0,
+                    window_id: 0,
+                    which: 0,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            } else {
+                Event::MouseButtonUp {
+                    timestamp: 0,
+                    window_id: 0,
+                    which: 0,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            }
+        }
+        "mouse_motion" => Event::MouseMotion {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mousestate: MouseState::from_sdl_state(0),
+            x: next_value(fields)?,
+            y: next_value(fields)?,
+            xrel: next_value(fields)?,
+            yrel: next_value(fields)?,
+        },
+        "mouse_wheel" => {
+            let x = next_value(fields)?;
+            let y = next_value(fields)?;
+            Event::MouseWheel {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                x,
+                y,
+                direction: MouseWheelDirection::Normal,
+                precise_x: x as f32,
+                precise_y: y as f32,
+                mouse_x: 0,
+                mouse_y: 0,
+            }
+        }
+        "resize" => Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::Resized(next_value(fields)?, next_value(fields)?),
+        },
+        "quit" => Event::Quit { timestamp: 0 },
+        _ => return Err(format!("unknown event: {}", kind)),
+    };
+
+    match fields.next() {
+        Some(field) => Err(format!("unexpected value: {}", field)),
+        None => Ok(event),
+    }
+}
+
+// `Scancode::from_i32` transmutes the value into SDL's scancode enum, which is
+// undefined behaviour for anything SDL doesn't define. Recordings are plain text
+// that can be edited or damaged, so the value is checked first.
+fn parse_scancode(value: i32) -> Result<Option<Scancode>, String> {
+    if SCANCODE_VALUES.iter().any(|range| range.contains(&value)) {
+        Ok(Scancode::from_i32(value))
+    } else {
+        Err(format!("invalid scancode: {}", value))
+    }
+}
+
+fn next_value<T: std::str::FromStr>(fields: &mut SplitWhitespace) -> Result<T, String> {
+    let field = fields.next().ok_or_else(|| "missing value".to_string())?;
+    field
+        .parse()
+        .map_err(|_| format!("invalid value: {}", field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_survive_a_round_trip() {
+        let events = vec![
+            Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Some(Keycode::W),
+                scancode: Some(Scancode::W),
+                keymod: Mod::NOMOD,
+                repeat: false,
+            },
+            Event::MouseMotion {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(0),
+                x: 640,
+                y: 360,
+                xrel: -3,
+                yrel: 7,
+            },
+            Event::MouseButtonUp {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: MouseButton::Middle,
+                clicks: 1,
+                x: 10,
+                y: 20,
+            },
+            Event::Window {
+                timestamp: 0,
+                window_id: 0,
+                win_event: WindowEvent::Resized(800, 600),
+            },
+        ];
+        let recording = "tick_rate 30\n".to_string()
+            + &format_frame(1.0 / 60.0, &events)
+            + &format_frame(0.1, &[]);
+
+        let recording = parse_recording(&recording).unwrap();
+        assert_eq!(recording.tick_rate, 30.0);
+        assert_eq!(
+            recording.frames,
+            vec![
+                RecordedFrame {
+                    delta: 1.0 / 60.0,
+                    events
+                },
+                RecordedFrame {
+                    delta: 0.1,
+                    events: vec![]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn recording_errors_name_the_line() {
+        let error = parse_recording("frame 0.1\nmouse_wheel 0").err();
+        assert_eq!(error.as_deref(), Some("line 2: missing value"));
+        assert!(parse_recording("quit").is_err());
+        assert!(parse_recording("frame 0.1\njump").is_err());
+    }
+
+    #[test]
+    fn corrupt_scancodes_are_rejected() {
+        let recording = |scancode: i32| format!("frame 0.1\nkey_down 119 {} false", scancode);
+
+        let error = parse_recording(&recording(100000)).err();
+        assert_eq!(error.as_deref(), Some("line 2: invalid scancode: 100000"));
+        // Negative, in a gap between defined scancodes and SDL_NUM_SCANCODES itself
+        for scancode in [-1, 2, 130, 512] {
+            assert!(parse_recording(&recording(scancode)).is_err());
+        }
+
+        // Unknown keys have no scancode, defined ones map to theirs
+        let events = |scancode: i32| {
+            parse_recording(&recording(scancode)).unwrap().frames[0]
+                .events
+                .clone()
+        };
+        assert!(matches!(
+            events(0)[0],
+            Event::KeyDown { scancode: None, .. }
+        ));
+        assert!(matches!(
+            events(Scancode::Sleep as i32)[0],
+            Event::KeyDown {
+                scancode: Some(Scancode::Sleep),
+                ..
+            }
+        ));
+    }
+}
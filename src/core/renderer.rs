@@ -0,0 +1,725 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use sdl2::pixels::Color;
+
+use super::{
+    bvh::RayHit,
+    camera::{Camera, Projection},
+    frame_buffer::FrameBuffer,
+    frustum::Frustum,
+    math::{vector_cross_product, vector_dot_product, vector_len},
+    matrix_4x4::Matrix4X4,
+    ray::Ray,
+    scene_object::SceneObject,
+    triangle::Triangle,
+    vector_3d::Vector3D,
+    vector_4d::Vector4D,
+    vertex_batch::VertexBatch,
+};
+
+// The object and triangle under a screen position
+pub struct PickResult {
+    pub object: usize,
+    pub triangle: usize,
+    // World space hit point
+    pub point: Vector3D,
+    pub distance: f32,
+}
+
+// Culling statistics and stage timings of the last rendered frame
+#[derive(Clone)]
+pub struct FrameStats {
+    pub objects_drawn: usize,
+    pub objects_culled: usize,
+    pub triangles_culled: usize,
+    // Triangles of the drawn objects going into the pipeline, and the ones
+    // that are rasterized after culling and clipping
+    pub triangles_in: usize,
+    pub triangles_out: usize,
+    // Triangles cut or removed by the near plane or the screen edges, each counted once
+    pub triangles_clipped: usize,
+    // Object culling, picking detail levels and transforming the vertices
+    pub transform_time: Duration,
+    // Backface culling and lighting
+    pub shade_time: Duration,
+    // Near plane and screen edge clipping, including projection
+    pub clip_time: Duration,
+    pub sort_time: Duration,
+    pub raster_time: Duration,
+    // Set by whoever shows the frame
+    pub present_time: Duration,
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        FrameStats {
+            objects_drawn: 0,
+            objects_culled: 0,
+            triangles_culled: 0,
+            triangles_in: 0,
+            triangles_out: 0,
+            triangles_clipped: 0,
+            transform_time: Duration::ZERO,
+            shade_time: Duration::ZERO,
+            clip_time: Duration::ZERO,
+            sort_time: Duration::ZERO,
+            raster_time: Duration::ZERO,
+            present_time: Duration::ZERO,
+        }
+    }
+}
+
+// How the triangles that survive culling and clipping end up on screen
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RenderMode {
+    // Only the edges of triangles, in their shaded color
+    Wireframe,
+    Solid,
+    // Solid with the edges drawn on top
+    SolidWireframe,
+    // Triangles colored by the direction they face in world space
+    Normals,
+    // The depth buffer as shades of gray, white is nearest
+    Depth,
+    // How often every pixel is drawn, from blue for once to red for many times
+    Overdraw,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 6] = [
+        RenderMode::Wireframe,
+        RenderMode::Solid,
+        RenderMode::SolidWireframe,
+        RenderMode::Normals,
+        RenderMode::Depth,
+        RenderMode::Overdraw,
+    ];
+
+    // Name used on the command line and in the HUD
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderMode::Wireframe => "wireframe",
+            RenderMode::Solid => "solid",
+            RenderMode::SolidWireframe => "solid-wireframe",
+            RenderMode::Normals => "normals",
+            RenderMode::Depth => "depth",
+            RenderMode::Overdraw => "overdraw",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RenderMode> {
+        RenderMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    // The mode after this one in `ALL`, wrapping around
+    pub fn next(&self) -> RenderMode {
+        let index = RenderMode::ALL
+            .iter()
+            .position(|mode| mode == self)
+            .unwrap_or(0);
+        RenderMode::ALL[(index + 1) % RenderMode::ALL.len()]
+    }
+}
+
+// Which side of triangles is skipped, the front is the side their normal points to
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CullMode {
+    Off,
+    Back,
+    Front,
+}
+
+impl CullMode {
+    pub const ALL: [CullMode; 3] = [CullMode::Off, CullMode::Back, CullMode::Front];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CullMode::Off => "off",
+            CullMode::Back => "back",
+            CullMode::Front => "front",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CullMode> {
+        CullMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    pub fn next(&self) -> CullMode {
+        match self {
+            CullMode::Off => CullMode::Back,
+            CullMode::Back => CullMode::Front,
+            CullMode::Front => CullMode::Off,
+        }
+    }
+}
+
+// The software rendering pipeline. Draws scene objects into a frame buffer,
+// without needing a window.
+pub struct Renderer {
+    pub frame_buffer: FrameBuffer,
+    projection_matrix: Matrix4X4,
+    view_matrix: Matrix4X4,
+    stats: FrameStats,
+    // Reused every frame, so transforming doesn't allocate
+    world_vertices: VertexBatch,
+    view_vertices: VertexBatch,
+    // Highlighted on top of the frame
+    pub selected: Option<PickResult>,
+    pub render_mode: RenderMode,
+    pub cull_mode: CullMode,
+    // Times every pixel was drawn, for the overdraw mode
+    overdraw: Vec<u32>,
+}
+
+impl Renderer {
+    pub fn new(size_x: u32, size_y: u32) -> Renderer {
+        Renderer {
+            frame_buffer: FrameBuffer::new(size_x, size_y),
+            projection_matrix: Camera::new().projection_matrix(size_y as f32 / size_x as f32),
+            view_matrix: Matrix4X4::from_identity(),
+            stats: FrameStats::new(),
+            world_vertices: VertexBatch::new(),
+            view_vertices: VertexBatch::new(),
+            selected: None,
+            render_mode: RenderMode::Solid,
+            cull_mode: CullMode::Back,
+            overdraw: vec![],
+        }
+    }
+
+    // Draws a frame of the objects, seen through `camera`
+    pub fn render(&mut self, objects: &[SceneObject], camera: &Camera) {
+        self.frame_buffer.clear();
+        let aspect_ratio = self.frame_buffer.height as f32 / self.frame_buffer.width as f32;
+        self.view_matrix = camera.view_matrix();
+        self.projection_matrix = camera.projection_matrix(aspect_ratio);
+        let view_matrix = &self.view_matrix;
+
+        // Clip against the camera's near plane in view space
+        let near_plane = (
+            Vector3D::from_coords(0.0, 0.0, camera.near_plane),
+            Vector3D::from_coords(0.0, 0.0, 1.0),
+        );
+        // With a parallel projection every triangle is seen from the same direction
+        let look_direction = camera.look_direction();
+
+        let frustum = Frustum::from_matrices(view_matrix, &self.projection_matrix);
+        self.stats = FrameStats::new();
+
+        let mut viewed_triangles: Vec<Triangle> = Vec::new();
+
+        // Do all transformations, timing them apart from shading the triangles
+        for object in objects {
+            let stage_start = Instant::now();
+            let world_matrix = object.world_matrix();
+            let lod_chain = object.lod_chain();
+
+            // Skip the whole object when its bounding sphere is outside the view. A
+            // scale of 0 flattens it to nothing.
+            let Some(normal_matrix) = world_matrix.normal_matrix() else {
+                self.stats.objects_culled += 1;
+                self.stats.transform_time += stage_start.elapsed();
+                continue;
+            };
+            let world_center = &world_matrix * &lod_chain.center;
+            let radius = object.bounding_radius();
+            if !frustum.intersects_sphere(&world_center, radius) {
+                self.stats.objects_culled += 1;
+                self.stats.transform_time += stage_start.elapsed();
+                continue;
+            }
+            self.stats.objects_drawn += 1;
+
+            // Pick a level of detail based on how large the object is on screen
+            let center = view_matrix * &world_center;
+            let projected_radius =
+                radius * self.projection_matrix.content[1][1] * self.frame_buffer.height as f32;
+            let projected_diameter = match camera.projection {
+                Projection::Perspective if center.z > radius => projected_radius / center.z,
+                Projection::Perspective => f32::INFINITY,
+                Projection::Orthographic => projected_radius,
+            };
+            let level = lod_chain.select(projected_diameter);
+            let triangle_count = lod_chain.levels[level].triangles.len();
+            self.stats.triangles_in += triangle_count;
+
+            // Only visit triangles in BVH nodes that are inside the view
+            let visible_triangles =
+                lod_chain.bvhs[level].query_frustum(&frustum.to_object_space(&world_matrix));
+            self.stats.triangles_culled += triangle_count - visible_triangles.len();
+
+            // Transform the vertices of the visible triangles at once, to world space
+            // for lighting and culling and to view space for clipping and projection
+            let world_view_matrix = &world_matrix * view_matrix;
+            let vertex_batch = &lod_chain.vertex_batches[level];
+            vertex_batch.transform_triangles_into(
+                &world_matrix,
+                &visible_triangles,
+                &mut self.world_vertices,
+            );
+            vertex_batch.transform_triangles_into(
+                &world_view_matrix,
+                &visible_triangles,
+                &mut self.view_vertices,
+            );
+            self.stats.transform_time += stage_start.elapsed();
+
+            let stage_start = Instant::now();
+            let object_triangles = &lod_chain.levels[level].triangles;
+            let winding = world_matrix.determinant().signum();
+            for &triangle_index in &visible_triangles {
+                let first_vertex = triangle_index * 3;
+                let mut transformed_triangle = Triangle::new();
+                let mut viewed_triangle = Triangle::new();
+
+                for i in 0..3 {
+                    transformed_triangle.vectors[i] = self.world_vertices.point(first_vertex + i);
+                    viewed_triangle.vectors[i] = self.view_vertices.point(first_vertex + i);
+                }
+
+                // Calculate normals in object space, from the cross product of two edges.
+                // Triangles without an area have no normal and cover no pixels, so they
+                // are skipped.
+                let [a, b, c] = object_triangles[triangle_index].vectors;
+                let object_normal = vector_cross_product(&(b - a), &(c - a));
+                if vector_len(&object_normal) <= 0.0 {
+                    continue;
+                }
+                let normal = (&normal_matrix * &Vector4D::from_direction(&object_normal)).xyz();
+                // A mirroring scale turns the winding around
+                let normal = normal.from_normalise() * winding;
+
+                let camera_ray = match camera.projection {
+                    Projection::Perspective => transformed_triangle.vectors[0] - camera.position,
+                    Projection::Orthographic => look_direction,
+                };
+
+                let facing_camera = vector_dot_product(&normal, &camera_ray) < 0.0;
+                let culled = match self.cull_mode {
+                    CullMode::Off => false,
+                    CullMode::Back => !facing_camera,
+                    CullMode::Front => facing_camera,
+                };
+                if culled {
+                    continue;
+                }
+
+                viewed_triangle.base_color = if self.render_mode == RenderMode::Normals {
+                    normal_color(&normal)
+                } else {
+                    // Calculate illumination
+                    let light_direction = Vector3D::from_coords(0.0, 0.0, -1.0).from_normalise();
+                    let dot_product = f32::max(0.1, vector_dot_product(&light_direction, &normal));
+                    get_color(dot_product, viewed_triangle.base_color)
+                };
+                viewed_triangles.push(viewed_triangle);
+            }
+            self.stats.shade_time += stage_start.elapsed();
+        }
+
+        // Clip viewed triangles against the near plane, project them and clip
+        // them against the edges of the screen
+        let stage_start = Instant::now();
+        let mut final_triangles: Vec<Triangle> = Vec::new();
+        for viewed_triangle in &viewed_triangles {
+            let near_triangles = viewed_triangle.clip_against_plane(near_plane.0, near_plane.1);
+            let mut clipped =
+                near_triangles.len() != 1 || near_triangles[0].vectors != viewed_triangle.vectors;
+
+            for near_triangle in &near_triangles {
+                let projected_triangle = self.project_triangle(near_triangle);
+                let vectors = projected_triangle.vectors;
+                let screen_triangles = self.clip_to_screen(projected_triangle);
+                clipped |= screen_triangles.len() != 1 || screen_triangles[0].vectors != vectors;
+                final_triangles.extend(screen_triangles);
+            }
+
+            // Counted once, however many planes cut the triangle
+            if clipped {
+                self.stats.triangles_clipped += 1;
+            }
+        }
+        self.stats.clip_time = stage_start.elapsed();
+        self.stats.triangles_out = final_triangles.len();
+
+        // Sort the triangles from back to front
+        let stage_start = Instant::now();
+        final_triangles.sort_by(|t1, t2| {
+            let z1 = (t1.vectors[0].z + t1.vectors[1].z + t1.vectors[2].z) / 3.0;
+            let z2 = (t2.vectors[0].z + t2.vectors[1].z + t2.vectors[2].z) / 3.0;
+
+            z2.partial_cmp(&z1).unwrap_or_else(|| {
+                if z1.is_nan() {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Greater
+                }
+            })
+        });
+        self.stats.sort_time = stage_start.elapsed();
+
+        // Rasterize everything to the screen
+        let stage_start = Instant::now();
+        match self.render_mode {
+            RenderMode::Wireframe => {
+                for final_triangle in &final_triangles {
+                    self.frame_buffer
+                        .draw_wireframe(final_triangle, final_triangle.base_color);
+                }
+            }
+            RenderMode::Solid | RenderMode::Normals => {
+                for final_triangle in &final_triangles {
+                    self.frame_buffer.draw_filled_triangle(final_triangle);
+                }
+            }
+            RenderMode::SolidWireframe => {
+                // Back to front, so nearer triangles cover the edges behind them
+                for final_triangle in &final_triangles {
+                    self.frame_buffer.draw_filled_triangle(final_triangle);
+                    self.frame_buffer
+                        .draw_wireframe(final_triangle, Color::RGBA(255, 255, 255, 96));
+                }
+            }
+            RenderMode::Depth => {
+                for final_triangle in &final_triangles {
+                    self.frame_buffer.draw_filled_triangle(final_triangle);
+                }
+                self.frame_buffer.draw_depth();
+            }
+            RenderMode::Overdraw => {
+                self.overdraw.clear();
+                self.overdraw.resize(self.frame_buffer.depth.len(), 0);
+                for final_triangle in &final_triangles {
+                    self.frame_buffer
+                        .count_overdraw(final_triangle, &mut self.overdraw);
+                }
+                self.frame_buffer.draw_heatmap(&self.overdraw, 8);
+            }
+        }
+        self.stats.raster_time = stage_start.elapsed();
+
+        self.draw_selection(objects, near_plane);
+    }
+
+    // Clips a projected triangle against the edges of the screen
+    pub fn clip_to_screen(&self, triangle: Triangle) -> VecDeque<Triangle> {
+        let size_x = self.frame_buffer.width as f32;
+        let size_y = self.frame_buffer.height as f32;
+
+        // Define clipping planes
+        let clipping_planes = [
+            (
+                Vector3D::from_coords(0.0, 0.0, 0.0),
+                Vector3D::from_coords(0.0, 1.0, 0.0),
+            ), // Top
+            (
+                Vector3D::from_coords(0.0, size_y - 1.0, 0.0),
+                Vector3D::from_coords(0.0, -1.0, 0.0),
+            ), // Bottom
+            (
+                Vector3D::from_coords(0.0, 0.0, 0.0),
+                Vector3D::from_coords(1.0, 0.0, 0.0),
+            ), // Left
+            (
+                Vector3D::from_coords(size_x - 1.0, 0.0, 0.0),
+                Vector3D::from_coords(-1.0, 0.0, 0.0),
+            ), // Right
+        ];
+
+        let mut triangle_queue: VecDeque<Triangle> = VecDeque::new();
+        triangle_queue.push_back(triangle);
+
+        for (plane_position, plane_normal) in &clipping_planes {
+            let mut next_queue = VecDeque::new();
+
+            while let Some(test_triangle) = triangle_queue.pop_front() {
+                // Clip triangle against the current plane
+                let clipped_triangles =
+                    test_triangle.clip_against_plane(*plane_position, *plane_normal);
+
+                // Add clipped triangles to the next queue
+                for clipped_triangle in clipped_triangles {
+                    next_queue.push_back(clipped_triangle);
+                }
+            }
+
+            // Swap queues for the next iteration
+            triangle_queue = next_queue;
+        }
+
+        triangle_queue
+    }
+
+    // Casts a ray from the camera through a pixel and returns the closest hit.
+    // Objects are tested at full detail, whatever level is being drawn.
+    pub fn pick(
+        &self,
+        objects: &[SceneObject],
+        screen_x: i32,
+        screen_y: i32,
+    ) -> Option<PickResult> {
+        // Screen to normalised device coordinates, undoing the flip done when rasterizing
+        let x = 1.0 - 2.0 * (screen_x as f32 + 0.5) / self.frame_buffer.width as f32;
+        let y = 1.0 - 2.0 * (screen_y as f32 + 0.5) / self.frame_buffer.height as f32;
+
+        // Unproject points on the near and far plane back into world space
+        let inverse_projection = self.projection_matrix.inverse()?;
+        let camera_matrix = self.view_matrix.quick_inverse();
+        let unproject = |z: f32| {
+            let point = &inverse_projection * &Vector4D::from_coords(x, y, z, 1.0);
+            &camera_matrix * &point.perspective_divide()
+        };
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+        let direction = far - near;
+
+        let mut closest: Option<PickResult> = None;
+        for (object_index, object) in objects.iter().enumerate() {
+            // Move the ray into object space instead of moving every triangle
+            let Some(inverse_world) = object.world_matrix().inverse() else {
+                continue;
+            };
+            let origin = &inverse_world * &near;
+            let ray = Ray::new(origin, &inverse_world * &far - origin);
+
+            let Some(RayHit {
+                triangle, point, ..
+            }) = object.lod_chain().bvhs[0].intersect_ray(&object.lod_chain().levels[0], &ray)
+            else {
+                continue;
+            };
+
+            let point = &object.world_matrix() * &point;
+            let distance = vector_dot_product(&(point - near), &direction.from_normalise());
+            if closest.as_ref().is_none_or(|pick| distance < pick.distance) {
+                closest = Some(PickResult {
+                    object: object_index,
+                    triangle,
+                    point,
+                    distance,
+                });
+            }
+        }
+
+        closest
+    }
+
+    fn draw_selection(&mut self, objects: &[SceneObject], near_plane: (Vector3D, Vector3D)) {
+        let Some(selected) = &self.selected else {
+            return;
+        };
+        let Some(object) = objects.get(selected.object) else {
+            return;
+        };
+        let world_matrix = object.world_matrix();
+        let triangle = &object.lod_chain().levels[0].triangles[selected.triangle];
+
+        let mut viewed_triangle = Triangle::new();
+        for (viewed, vector) in viewed_triangle.vectors.iter_mut().zip(&triangle.vectors) {
+            *viewed = &self.view_matrix * &(&world_matrix * vector);
+        }
+
+        let clipped_triangles = viewed_triangle.clip_against_plane(near_plane.0, near_plane.1);
+        for clipped_triangle in &clipped_triangles {
+            let projected_triangle = self.project_triangle(clipped_triangle);
+            // Red wireframe
+            self.frame_buffer
+                .draw_wireframe(&projected_triangle, Color::RGB(255, 0, 0));
+        }
+    }
+
+    // Projects a view space triangle to screen coordinates
+    fn project_triangle(&self, triangle: &Triangle) -> Triangle {
+        let mut projected_triangle = Triangle::new();
+        projected_triangle.base_color = triangle.base_color;
+
+        for (projected, vector) in projected_triangle.vectors.iter_mut().zip(&triangle.vectors) {
+            // Project from 3D to 2D
+            let mut point =
+                (&self.projection_matrix * &Vector4D::from_point(vector)).perspective_divide();
+
+            // Scale into view
+            point.x = (point.x + 1.0) * 0.5 * self.frame_buffer.width as f32;
+            point.y = (point.y + 1.0) * 0.5 * self.frame_buffer.height as f32;
+            *projected = point;
+        }
+
+        projected_triangle
+    }
+
+    // The projection follows the new aspect ratio from the next frame on
+    pub fn resize(&mut self, size_x: u32, size_y: u32) {
+        self.frame_buffer.resize(size_x, size_y);
+        self.frame_buffer.clear();
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.stats
+    }
+}
+
+// Maps the axes of a unit normal to the color channels, so -1 is 0 and 1 is 255
+fn normal_color(normal: &Vector3D) -> Color {
+    let channel = |value: f32| ((value.clamp(-1.0, 1.0) * 0.5 + 0.5) * 255.0) as u8;
+    Color::RGB(channel(normal.x), channel(normal.y), channel(normal.z))
+}
+
+fn get_color(lum: f32, base_color: Color) -> Color {
+    // Clamp luminance between 0.0 and 1.0
+    let luminance = lum.clamp(0.0, 1.0);
+
+    // Calculate the new color components
+    let new_r = (base_color.r as f32 * luminance) as u8;
+    let new_g = (base_color.g as f32 * luminance) as u8;
+    let new_b = (base_color.b as f32 * luminance) as u8;
+
+    Color::RGB(new_r, new_g, new_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{mesh::Mesh, scene_object::SceneObject};
+
+    fn object(triangles: Vec<Triangle>) -> SceneObject {
+        let mesh = Mesh {
+            triangles,
+            polygons: vec![],
+        };
+        SceneObject::new(mesh, Vector3D::from_coords(0.0, 0.0, 4.0), 1)
+    }
+
+    // A triangle facing the camera around (x, 0) in the object's plane
+    fn triangle_at(x: f32) -> Triangle {
+        Triangle::new_from_vectors([
+            Vector3D::from_coords(x - 0.5, -0.5, 0.0),
+            Vector3D::from_coords(x, 0.5, 0.0),
+            Vector3D::from_coords(x + 0.5, -0.5, 0.0),
+        ])
+    }
+
+    fn count_pixels(frame_buffer: &FrameBuffer, color: Color) -> usize {
+        (0..frame_buffer.height as i32)
+            .flat_map(|y| (0..frame_buffer.width as i32).map(move |x| (x, y)))
+            .filter(|(x, y)| frame_buffer.get_pixel(*x, *y) == Some(color))
+            .count()
+    }
+
+    #[test]
+    fn zero_area_triangles_are_skipped() {
+        let mut renderer = Renderer::new(64, 64);
+        renderer.cull_mode = CullMode::Off;
+        let point = Vector3D::from_coords(0.0, 0.0, 0.0);
+        let collinear = Triangle::new_from_vectors([
+            point,
+            Vector3D::from_coords(1.0, 1.0, 0.0),
+            Vector3D::from_coords(2.0, 2.0, 0.0),
+        ]);
+        let collapsed = Triangle::new_from_vectors([point, point, point]);
+        renderer.render(&[object(vec![collinear, collapsed])], &Camera::new());
+
+        assert_eq!(renderer.stats().triangles_in, 2);
+        assert_eq!(renderer.stats().triangles_out, 0);
+    }
+
+    #[test]
+    fn scaled_objects_are_shaded_with_their_world_normal() {
+        let mut renderer = Renderer::new(64, 64);
+        renderer.render_mode = RenderMode::Normals;
+        // Faces the camera and to the side, stretched along x that turns it further
+        let tilted = Triangle::new_from_vectors([
+            Vector3D::from_coords(-0.5, -0.5, 0.5),
+            Vector3D::from_coords(0.0, 0.5, 0.0),
+            Vector3D::from_coords(0.5, -0.5, -0.5),
+        ]);
+        let expected = [
+            normal_color(&Vector3D::from_coords(-1.0, 0.0, -4.0).from_normalise()),
+            // Mirrored, the other side faces the camera
+            normal_color(&Vector3D::from_coords(-1.0, 0.0, 4.0).from_normalise()),
+        ];
+
+        for (scale_x, expected) in [(4.0, expected[0]), (-4.0, expected[1])] {
+            let mut scaled = object(vec![tilted.clone()]);
+            scaled.scale = Vector3D::from_coords(scale_x, 1.0, 1.0);
+            renderer.cull_mode = CullMode::Off;
+            renderer.render(&[scaled], &Camera::new());
+
+            let color = renderer.frame_buffer.get_pixel(32, 32).unwrap();
+            let difference = |a: u8, b: u8| (a as i32 - b as i32).abs();
+            assert!(difference(color.r, expected.r) <= 1);
+            assert!(difference(color.g, expected.g) <= 1);
+            assert!(difference(color.b, expected.b) <= 1);
+        }
+    }
+
+    #[test]
+    fn clipped_triangles_are_counted_once() {
+        let mut renderer = Renderer::new(64, 64);
+        renderer.cull_mode = CullMode::Off;
+        // Reaches behind the camera and far past both sides of the screen
+        let wide = Triangle::new_from_vectors([
+            Vector3D::from_coords(-20.0, -1.0, -5.0),
+            Vector3D::from_coords(0.0, 1.0, 1.0),
+            Vector3D::from_coords(20.0, -1.0, -5.0),
+        ]);
+        renderer.render(&[object(vec![wide, triangle_at(0.0)])], &Camera::new());
+
+        let stats = renderer.stats();
+        assert_eq!(stats.triangles_in, 2);
+        assert_eq!(stats.triangles_clipped, 1);
+        assert!(stats.triangles_out > 3);
+    }
+
+    #[test]
+    fn picks_the_triangle_drawn_at_a_pixel() {
+        let mut renderer = Renderer::new(64, 64);
+        let objects = [
+            object(vec![triangle_at(1.0)]),
+            object(vec![triangle_at(-1.0)]),
+        ];
+        renderer.render(&objects, &Camera::new());
+
+        // With a 90 degree view, x = 1 at a distance of 4 is a quarter of the way
+        // to the edge. The screen is flipped, so +x is on the left.
+        let black = Color::RGB(0, 0, 0);
+        for (screen_x, expected_object, expected_x) in [(24, 0, 1.0), (40, 1, -1.0)] {
+            assert_ne!(renderer.frame_buffer.get_pixel(screen_x, 32), Some(black));
+
+            let pick = renderer.pick(&objects, screen_x, 32).unwrap();
+            assert_eq!(pick.object, expected_object);
+            assert_eq!(pick.triangle, 0);
+            assert!((pick.point.x - expected_x).abs() < 0.1);
+            assert!(pick.point.y.abs() < 0.1);
+            assert!((pick.point.z - 4.0).abs() < 1e-4);
+        }
+
+        // Between the triangles and in the corner nothing is drawn or picked
+        for (screen_x, screen_y) in [(32, 32), (2, 2)] {
+            assert_eq!(
+                renderer.frame_buffer.get_pixel(screen_x, screen_y),
+                Some(black)
+            );
+            assert!(renderer.pick(&objects, screen_x, screen_y).is_none());
+        }
+    }
+
+    #[test]
+    fn selected_triangle_is_outlined() {
+        let mut renderer = Renderer::new(64, 64);
+        let objects = [object(vec![triangle_at(1.0)])];
+        let red = Color::RGB(255, 0, 0);
+        renderer.render(&objects, &Camera::new());
+        assert_eq!(count_pixels(&renderer.frame_buffer, red), 0);
+
+        renderer.selected = renderer.pick(&objects, 24, 32);
+        renderer.render(&objects, &Camera::new());
+        assert!(count_pixels(&renderer.frame_buffer, red) > 10);
+    }
+}
@@ -0,0 +1,74 @@
+use super::{
+    lod::LodChain, matrix_4x4::Matrix4X4, mesh::Mesh, quaternion::Quaternion, vector_3d::Vector3D,
+};
+
+// Every level multiplies the triangle count by 4
+pub const MAX_SUBDIVISION_LEVEL: usize = 3;
+
+// A mesh placed in the world, with its detail levels and subdivisions
+pub struct SceneObject {
+    pub position: Vector3D,
+    pub rotation: Quaternion,
+    // Along the object's own axes, before it is rotated
+    pub scale: Vector3D,
+    lod_levels: usize,
+    // Detail levels of every subdivision level that was used so far, so stepping
+    // back to one is instant
+    lod_chains: Vec<LodChain>,
+    subdivision_level: usize,
+}
+
+impl SceneObject {
+    pub fn new(mesh: Mesh, position: Vector3D, lod_levels: usize) -> SceneObject {
+        SceneObject {
+            position,
+            rotation: Quaternion::from_identity(),
+            scale: Vector3D::from_coords(1.0, 1.0, 1.0),
+            lod_levels,
+            lod_chains: vec![LodChain::new(mesh, lod_levels)],
+            subdivision_level: 0,
+        }
+    }
+
+    // Scales and rotates around the object's own origin, then moves it into place
+    pub fn world_matrix(&self) -> Matrix4X4 {
+        let scale = Matrix4X4::from_scale(self.scale.x, self.scale.y, self.scale.z);
+        &(&scale * &self.rotation.to_matrix())
+            * &Matrix4X4::from_translation(self.position.x, self.position.y, self.position.z)
+    }
+
+    // Radius of the bounding sphere of the current detail levels in world space
+    pub fn bounding_radius(&self) -> f32 {
+        let largest_scale = self
+            .scale
+            .x
+            .abs()
+            .max(self.scale.y.abs())
+            .max(self.scale.z.abs());
+        self.lod_chain().radius * largest_scale
+    }
+
+    pub fn subdivision_level(&self) -> usize {
+        self.subdivision_level
+    }
+
+    // Detail levels of the current subdivision level
+    pub fn lod_chain(&self) -> &LodChain {
+        &self.lod_chains[self.subdivision_level]
+    }
+
+    // Subdivides the original mesh `level` times, returns the level that is used
+    // after clamping it to `MAX_SUBDIVISION_LEVEL`
+    pub fn set_subdivision_level(&mut self, level: usize) -> usize {
+        let level = level.min(MAX_SUBDIVISION_LEVEL);
+
+        // Every level is subdivided from the previous one
+        while self.lod_chains.len() <= level {
+            let next = self.lod_chains.last().unwrap().levels[0].subdivide();
+            self.lod_chains.push(LodChain::new(next, self.lod_levels));
+        }
+
+        self.subdivision_level = level;
+        level
+    }
+}
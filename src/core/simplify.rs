@@ -0,0 +1,493 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use super::{
+    indexed_mesh::IndexedMesh,
+    math::{vector_cross_product, vector_dot_product, vector_len},
+    mesh::Mesh,
+    vector_3d::Vector3D,
+};
+
+// Boundary edges get an extra constraint plane with this weight so open
+// borders (like the rim of the teapot lid) don't shrink while simplifying
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+// Symmetric 4x4 error quadric, stored as its 10 unique values:
+// a², ab, ac, ad, b², bc, bd, c², cd, d²
+#[derive(Clone, Copy)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn new() -> Quadric {
+        Quadric([0.0; 10])
+    }
+
+    fn from_plane(a: f64, b: f64, c: f64, d: f64, weight: f64) -> Quadric {
+        Quadric([
+            a * a * weight,
+            a * b * weight,
+            a * c * weight,
+            a * d * weight,
+            b * b * weight,
+            b * c * weight,
+            b * d * weight,
+            c * c * weight,
+            c * d * weight,
+            d * d * weight,
+        ])
+    }
+
+    fn add(&self, other: &Quadric) -> Quadric {
+        let mut result = Quadric::new();
+        for i in 0..10 {
+            result.0[i] = self.0[i] + other.0[i];
+        }
+        result
+    }
+
+    // Sum of squared distances from the point to all planes in the quadric
+    fn error(&self, v: &Vector3D) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (v.x as f64, v.y as f64, v.z as f64);
+
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    // Solves for the point with the lowest error, if the quadric isn't singular
+    fn optimal_position(&self) -> Option<Vector3D> {
+        let q = &self.0;
+        let determinant = q[0] * (q[4] * q[7] - q[5] * q[5]) - q[1] * (q[1] * q[7] - q[5] * q[2])
+            + q[2] * (q[1] * q[5] - q[4] * q[2]);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        // Cramer's rule on the upper 3x3 block with -(ad, bd, cd) as right-hand side
+        let (bx, by, bz) = (-q[3], -q[6], -q[8]);
+        let x = (bx * (q[4] * q[7] - q[5] * q[5]) - q[1] * (by * q[7] - q[5] * bz)
+            + q[2] * (by * q[5] - q[4] * bz))
+            / determinant;
+        let y = (q[0] * (by * q[7] - bz * q[5]) - bx * (q[1] * q[7] - q[5] * q[2])
+            + q[2] * (q[1] * bz - by * q[2]))
+            / determinant;
+        let z = (q[0] * (q[4] * bz - q[5] * by) - q[1] * (q[1] * bz - by * q[2])
+            + bx * (q[1] * q[5] - q[4] * q[2]))
+            / determinant;
+
+        Some(Vector3D::from_coords(x as f32, y as f32, z as f32))
+    }
+}
+
+struct Collapse {
+    cost: f64,
+    // How far the collapse moves the surface, see `Simplifier::distance`
+    distance: f64,
+    position: Vector3D,
+    vertices: (usize, usize),
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed, so the binary heap pops the cheapest collapse first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Simplifier {
+    vertices: Vec<Vector3D>,
+    faces: Vec<[usize; 3]>,
+    quadrics: Vec<Quadric>,
+    // The same planes without the boundary weight, and how many there are
+    distance_quadrics: Vec<Quadric>,
+    plane_counts: Vec<u32>,
+    vertex_faces: Vec<Vec<usize>>,
+    vertex_alive: Vec<bool>,
+    face_alive: Vec<bool>,
+    versions: Vec<u32>,
+    heap: BinaryHeap<Collapse>,
+}
+
+impl Simplifier {
+    fn new(mesh: &Mesh) -> Simplifier {
+        let indexed = IndexedMesh::from_mesh(mesh, 0.0);
+        // In a fixed order, so collapses that cost the same are done in the same
+        // order every time
+        let mut edge_faces: Vec<((usize, usize), Vec<usize>)> =
+            indexed.edge_faces().into_iter().collect();
+        edge_faces.sort_unstable_by_key(|(edge, _)| *edge);
+        let vertex_count = indexed.vertices.len();
+        let mut simplifier = Simplifier {
+            quadrics: vec![Quadric::new(); vertex_count],
+            distance_quadrics: vec![Quadric::new(); vertex_count],
+            plane_counts: vec![0; vertex_count],
+            vertex_faces: vec![vec![]; vertex_count],
+            vertex_alive: vec![true; vertex_count],
+            face_alive: vec![true; indexed.faces.len()],
+            versions: vec![0; vertex_count],
+            heap: BinaryHeap::new(),
+            vertices: indexed.vertices,
+            faces: vec![],
+        };
+
+        // Accumulate the plane of every face into the quadrics of its vertices
+        for (face_index, face) in indexed.faces.iter().enumerate() {
+            if face[0] == face[1] || face[1] == face[2] || face[2] == face[0] {
+                simplifier.face_alive[face_index] = false;
+                continue;
+            }
+
+            if let Some((normal, d)) = simplifier.plane(face) {
+                let quadric = Quadric::from_plane(normal.0, normal.1, normal.2, d, 1.0);
+                for &vertex in face {
+                    simplifier.add_plane(vertex, &quadric, &quadric);
+                }
+            }
+            for &vertex in face {
+                simplifier.vertex_faces[vertex].push(face_index);
+            }
+        }
+        simplifier.faces = indexed.faces;
+
+        // Constrain open borders with a plane perpendicular to their only face
+        for &((a, b), ref faces) in &edge_faces {
+            if faces.len() != 1 {
+                continue;
+            }
+            let face = simplifier.faces[faces[0]];
+            let Some((normal, _)) = simplifier.plane(&face) else {
+                continue;
+            };
+
+            let edge = simplifier.vertices[b] - simplifier.vertices[a];
+            let face_normal =
+                Vector3D::from_coords(normal.0 as f32, normal.1 as f32, normal.2 as f32);
+            let border_normal = vector_cross_product(&edge, &face_normal);
+            let length = vector_len(&border_normal);
+            if length <= 0.0 {
+                continue;
+            }
+            let border_normal = border_normal / length;
+            let d = -vector_dot_product(&border_normal, &simplifier.vertices[a]);
+            let plane = |weight: f64| {
+                Quadric::from_plane(
+                    border_normal.x as f64,
+                    border_normal.y as f64,
+                    border_normal.z as f64,
+                    d as f64,
+                    weight,
+                )
+            };
+            let (weighted, unweighted) = (plane(BOUNDARY_WEIGHT), plane(1.0));
+            simplifier.add_plane(a, &weighted, &unweighted);
+            simplifier.add_plane(b, &weighted, &unweighted);
+        }
+
+        for &((a, b), _) in &edge_faces {
+            simplifier.push_collapse(a, b);
+        }
+
+        simplifier
+    }
+
+    fn plane(&self, face: &[usize; 3]) -> Option<((f64, f64, f64), f64)> {
+        let line1 = self.vertices[face[1]] - self.vertices[face[0]];
+        let line2 = self.vertices[face[2]] - self.vertices[face[0]];
+        let normal = vector_cross_product(&line1, &line2);
+        let length = vector_len(&normal);
+        if length <= 0.0 {
+            return None;
+        }
+
+        let normal = normal / length;
+        let d = -vector_dot_product(&normal, &self.vertices[face[0]]);
+        Some((
+            (normal.x as f64, normal.y as f64, normal.z as f64),
+            d as f64,
+        ))
+    }
+
+    // `weighted` decides which collapses go first, `unweighted` how far they move
+    // the surface
+    fn add_plane(&mut self, vertex: usize, weighted: &Quadric, unweighted: &Quadric) {
+        self.quadrics[vertex] = self.quadrics[vertex].add(weighted);
+        self.distance_quadrics[vertex] = self.distance_quadrics[vertex].add(unweighted);
+        self.plane_counts[vertex] += 1;
+    }
+
+    // Root mean square distance from `position` to the original planes around `a`
+    // and `b`, in the units of the mesh
+    fn distance(&self, a: usize, b: usize, position: &Vector3D) -> f64 {
+        let quadric = self.distance_quadrics[a].add(&self.distance_quadrics[b]);
+        let plane_count = (self.plane_counts[a] + self.plane_counts[b]).max(1);
+        (quadric.error(position).max(0.0) / plane_count as f64).sqrt()
+    }
+
+    fn push_collapse(&mut self, a: usize, b: usize) {
+        let quadric = self.quadrics[a].add(&self.quadrics[b]);
+        let midpoint = (self.vertices[a] + self.vertices[b]) * 0.5;
+
+        // Fall back on the best of the endpoints and the midpoint
+        let mut candidates = vec![self.vertices[a], self.vertices[b], midpoint];
+        if let Some(optimal) = quadric.optimal_position() {
+            candidates.insert(0, optimal);
+        }
+
+        let (cost, position) = candidates
+            .into_iter()
+            .map(|candidate| (quadric.error(&candidate), candidate))
+            .min_by(|(e1, _), (e2, _)| e1.total_cmp(e2))
+            .unwrap();
+
+        self.heap.push(Collapse {
+            cost: cost.max(0.0),
+            distance: self.distance(a, b, &position),
+            position,
+            vertices: (a, b),
+            versions: (self.versions[a], self.versions[b]),
+        });
+    }
+
+    // Moving a vertex must not turn any of its remaining faces inside-out
+    fn collapse_flips_faces(&self, vertex: usize, other: usize, position: &Vector3D) -> bool {
+        for &face_index in &self.vertex_faces[vertex] {
+            let face = &self.faces[face_index];
+            if !self.face_alive[face_index] || face.contains(&other) {
+                continue;
+            }
+
+            let mut moved = [
+                self.vertices[face[0]],
+                self.vertices[face[1]],
+                self.vertices[face[2]],
+            ];
+            let before = vector_cross_product(&(moved[1] - moved[0]), &(moved[2] - moved[0]));
+            for (i, &index) in face.iter().enumerate() {
+                if index == vertex {
+                    moved[i] = *position;
+                }
+            }
+            let after = vector_cross_product(&(moved[1] - moved[0]), &(moved[2] - moved[0]));
+
+            if vector_dot_product(&before, &after) <= 0.0 {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn run(&mut self, target_triangles: usize, max_error: f64) {
+        let mut face_count = self.face_alive.iter().filter(|alive| **alive).count();
+
+        while face_count > target_triangles {
+            let Some(collapse) = self.heap.pop() else {
+                break;
+            };
+            let (a, b) = collapse.vertices;
+            if !self.vertex_alive[a]
+                || !self.vertex_alive[b]
+                || collapse.versions != (self.versions[a], self.versions[b])
+            {
+                continue;
+            }
+            // Cheaper collapses can still move the surface further, so keep looking
+            if collapse.distance > max_error {
+                continue;
+            }
+            if self.collapse_flips_faces(a, b, &collapse.position)
+                || self.collapse_flips_faces(b, a, &collapse.position)
+            {
+                continue;
+            }
+
+            // Merge b into a
+            self.vertices[a] = collapse.position;
+            self.quadrics[a] = self.quadrics[a].add(&self.quadrics[b]);
+            self.distance_quadrics[a] = self.distance_quadrics[a].add(&self.distance_quadrics[b]);
+            self.plane_counts[a] += self.plane_counts[b];
+            self.vertex_alive[b] = false;
+            self.versions[a] += 1;
+
+            let faces_of_b = std::mem::take(&mut self.vertex_faces[b]);
+            for face_index in faces_of_b {
+                if !self.face_alive[face_index] {
+                    continue;
+                }
+                let face = &mut self.faces[face_index];
+                if face.contains(&a) {
+                    self.face_alive[face_index] = false;
+                    face_count -= 1;
+                } else {
+                    for index in face.iter_mut() {
+                        if *index == b {
+                            *index = a;
+                        }
+                    }
+                    self.vertex_faces[a].push(face_index);
+                }
+            }
+            let face_alive = &self.face_alive;
+            self.vertex_faces[a].retain(|face_index| face_alive[*face_index]);
+
+            // Re-evaluate every edge around the merged vertex
+            let mut neighbours: Vec<usize> = self.vertex_faces[a]
+                .iter()
+                .flat_map(|face_index| self.faces[*face_index])
+                .filter(|vertex| *vertex != a)
+                .collect();
+            neighbours.sort_unstable();
+            neighbours.dedup();
+            for neighbour in neighbours {
+                self.push_collapse(a, neighbour);
+            }
+        }
+    }
+
+    fn into_mesh(self) -> Mesh {
+        IndexedMesh {
+            faces: self
+                .faces
+                .iter()
+                .zip(&self.face_alive)
+                .filter(|(_, alive)| **alive)
+                .map(|(face, _)| *face)
+                .collect(),
+            vertices: self.vertices,
+        }
+        .to_mesh()
+    }
+}
+
+// Quadric error metric decimation (Garland & Heckbert). Collapses edges until the mesh
+// has at most `target_triangles` triangles, or until every collapse left would move the
+// surface by more than `max_error`: the root mean square distance of the new vertex to the
+// original faces and open borders around it, in the units of the mesh.
+pub fn simplify_mesh(mesh: &Mesh, target_triangles: usize, max_error: f32) -> Mesh {
+    let mut simplifier = Simplifier::new(mesh);
+    simplifier.run(target_triangles, max_error as f64);
+    simplifier.into_mesh()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        matrix_4x4::Matrix4X4,
+        test_helpers::{cube, grid, tetrahedron},
+    };
+
+    // Closed, convex and curved everywhere
+    fn ball() -> Mesh {
+        tetrahedron().subdivide().subdivide().subdivide()
+    }
+
+    fn total_area(mesh: &Mesh) -> f32 {
+        mesh.triangles.iter().map(|triangle| triangle.area()).sum()
+    }
+
+    #[test]
+    fn reaches_target_triangle_count() {
+        let mesh = ball();
+        assert_eq!(mesh.triangles.len(), 256);
+
+        for target in [128, 50, 10] {
+            let simplified = mesh.simplify(target, f32::INFINITY);
+            // Every collapse on a closed mesh removes two triangles
+            assert!(simplified.triangles.len() <= target);
+            assert!(simplified.triangles.len() >= target - 1);
+        }
+    }
+
+    #[test]
+    fn stops_at_max_error() {
+        let mesh = ball();
+
+        // No collapse on a curved surface is free
+        assert_eq!(mesh.simplify(0, 0.0).triangles.len(), 256);
+
+        let coarse = mesh.simplify(0, 1e-3).triangles.len();
+        let coarser = mesh.simplify(0, 1e-2).triangles.len();
+        assert!(coarse < 256);
+        assert!(coarser < coarse);
+        assert!(coarser > 4);
+    }
+
+    #[test]
+    fn max_error_is_a_distance_in_model_units() {
+        let mesh = ball();
+        // A power of two, so scaling doesn't round differently
+        let mut larger = ball();
+        larger
+            .apply_transform(&Matrix4X4::from_scale(8.0, 8.0, 8.0))
+            .unwrap();
+
+        for max_error in [1e-3, 1e-2, 3e-2] {
+            assert_eq!(
+                larger.simplify(0, max_error * 8.0).triangles.len(),
+                mesh.simplify(0, max_error).triangles.len()
+            );
+        }
+    }
+
+    #[test]
+    fn flat_areas_collapse_for_free() {
+        let simplified = grid(8).simplify(0, 1e-6);
+        assert!(simplified.triangles.len() < 16);
+    }
+
+    #[test]
+    fn convex_mesh_keeps_facing_outwards() {
+        for mesh in [ball(), cube().subdivide().subdivide()] {
+            let (center, _) = mesh.bounding_sphere();
+            for target in [100, 20, 8] {
+                let simplified = mesh.simplify(target, f32::INFINITY);
+                for triangle in &simplified.triangles {
+                    let [v0, v1, v2] = &triangle.vectors;
+                    let normal = vector_cross_product(&(v1 - v0), &(v2 - v0));
+                    let outwards = (v0 + v1 + v2) / 3.0 - center;
+                    assert!(vector_dot_product(&normal, &outwards) > 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn open_borders_keep_their_shape() {
+        let mesh = grid(8);
+        assert_eq!(mesh.triangles.len(), 128);
+
+        let simplified = mesh.simplify(2, f32::INFINITY);
+        assert!(simplified.triangles.len() < 16);
+        // The border can only stay in place if the square isn't shrunk or bent
+        assert!((total_area(&simplified) - 1.0).abs() < 1e-3);
+        for triangle in &simplified.triangles {
+            for vector in &triangle.vectors {
+                assert!((0.0..=1.0).contains(&vector.x));
+                assert!((0.0..=1.0).contains(&vector.y));
+                assert!(vector.z.abs() < 1e-5);
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use sdl2::{
+    pixels::{Color, PixelFormatEnum},
+    surface::Surface,
+};
+
+// An image that can be drawn on a frame buffer, pixels with alpha below 255 are
+// blended with what is already there
+#[derive(Clone)]
+pub struct Sprite {
+    pub width: u32,
+    pub height: u32,
+    // Row by row, starting at the top left
+    pub pixels: Vec<Color>,
+}
+
+impl Sprite {
+    // A fully transparent sprite
+    pub fn new(width: u32, height: u32) -> Sprite {
+        Sprite {
+            width,
+            height,
+            pixels: vec![Color::RGBA(0, 0, 0, 0); (width * height) as usize],
+        }
+    }
+
+    pub fn from_bmp(filename: &str) -> Result<Sprite, String> {
+        let surface = Surface::load_bmp(filename)
+            .and_then(|surface| surface.convert_format(PixelFormatEnum::RGBA32))
+            .map_err(|err| format!("Error loading {}: {}", filename, err))?;
+
+        let mut sprite = Sprite::new(surface.width(), surface.height());
+        let pitch = surface.pitch() as usize;
+        surface.with_lock(|bytes| {
+            for y in 0..sprite.height as usize {
+                for x in 0..sprite.width as usize {
+                    // RGBA32 has the bytes in this order whatever the endianness
+                    let offset = y * pitch + x * 4;
+                    sprite.pixels[y * sprite.width as usize + x] = Color::RGBA(
+                        bytes[offset],
+                        bytes[offset + 1],
+                        bytes[offset + 2],
+                        bytes[offset + 3],
+                    );
+                }
+            }
+        });
+
+        Ok(sprite)
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        if x < self.width && y < self.height {
+            self.pixels[(y * self.width + x) as usize] = color;
+        }
+    }
+}
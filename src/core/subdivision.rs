@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use super::{
+    indexed_mesh::{IndexedMesh, VertexWelder},
+    mesh::Mesh,
+    triangle::Triangle,
+    vector_3d::Vector3D,
+};
+
+// Loop subdivision for triangle meshes: every triangle is split into 4 and all
+// vertices are moved towards a smooth limit surface. Open borders are kept as
+// a smooth curve, vertices on non-manifold edges are not moved.
+pub fn loop_subdivide(mesh: &Mesh) -> Mesh {
+    let mut indexed = IndexedMesh::from_mesh(mesh, 0.0);
+    indexed
+        .faces
+        .retain(|face| face[0] != face[1] && face[1] != face[2] && face[2] != face[0]);
+    let edge_faces = indexed.edge_faces();
+    let vertices = &indexed.vertices;
+    let faces = &indexed.faces;
+
+    let mut neighbours: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+    let mut border_neighbours: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+    let mut is_crease = vec![false; vertices.len()];
+    let mut new_vertices: Vec<Vector3D> = vertices.clone();
+    let mut edge_points: HashMap<(usize, usize), usize> = HashMap::new();
+
+    for (&(a, b), edge_face_indices) in &edge_faces {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+
+        let edge_point = if edge_face_indices.len() == 2 {
+            let c = opposite_vertex(&faces[edge_face_indices[0]], a, b);
+            let d = opposite_vertex(&faces[edge_face_indices[1]], a, b);
+            weighted_sum(&[
+                (&vertices[a], 3.0 / 8.0),
+                (&vertices[b], 3.0 / 8.0),
+                (&vertices[c], 1.0 / 8.0),
+                (&vertices[d], 1.0 / 8.0),
+            ])
+        } else {
+            if edge_face_indices.len() == 1 {
+                border_neighbours[a].push(b);
+                border_neighbours[b].push(a);
+            } else {
+                is_crease[a] = true;
+                is_crease[b] = true;
+            }
+            weighted_sum(&[(&vertices[a], 0.5), (&vertices[b], 0.5)])
+        };
+
+        new_vertices.push(edge_point);
+        edge_points.insert((a, b), new_vertices.len() - 1);
+    }
+
+    for (index, vertex) in vertices.iter().enumerate() {
+        if is_crease[index] || neighbours[index].is_empty() {
+            continue;
+        }
+
+        new_vertices[index] = match border_neighbours[index].len() {
+            0 => {
+                let n = neighbours[index].len() as f32;
+                let beta = if neighbours[index].len() == 3 {
+                    3.0 / 16.0
+                } else {
+                    3.0 / (8.0 * n)
+                };
+                let mut points = vec![(vertex, 1.0 - n * beta)];
+                points.extend(neighbours[index].iter().map(|i| (&vertices[*i], beta)));
+                weighted_sum(&points)
+            }
+            2 => weighted_sum(&[
+                (vertex, 3.0 / 4.0),
+                (&vertices[border_neighbours[index][0]], 1.0 / 8.0),
+                (&vertices[border_neighbours[index][1]], 1.0 / 8.0),
+            ]),
+            // Corners where several borders meet keep their position
+            _ => *vertex,
+        };
+    }
+
+    let edge_point = |a: usize, b: usize| edge_points[&(a.min(b), a.max(b))];
+    let mut new_faces: Vec<[usize; 3]> = Vec::with_capacity(faces.len() * 4);
+    for &[a, b, c] in faces {
+        let ab = edge_point(a, b);
+        let bc = edge_point(b, c);
+        let ca = edge_point(c, a);
+        new_faces.push([a, ab, ca]);
+        new_faces.push([ab, b, bc]);
+        new_faces.push([ca, bc, c]);
+        new_faces.push([ab, bc, ca]);
+    }
+
+    IndexedMesh {
+        vertices: new_vertices,
+        faces: new_faces,
+    }
+    .to_mesh()
+}
+
+// Catmull-Clark subdivision for polygon meshes: every n-sided face is split into
+// n quads. The result keeps its quads in `polygons`, so it can be subdivided again.
+pub fn catmull_clark_subdivide(mesh: &Mesh) -> Mesh {
+    let mut welder = VertexWelder::new(0.0);
+    let faces: Vec<Vec<usize>> = mesh
+        .polygons
+        .iter()
+        .map(|polygon| {
+            polygon
+                .iter()
+                .map(|vector| welder.index_of(vector))
+                .collect()
+        })
+        .filter(|face: &Vec<usize>| face.len() >= 3)
+        .collect();
+    let vertices = welder.vertices;
+
+    let face_points: Vec<Vector3D> = faces
+        .iter()
+        .map(|face| {
+            let weight = 1.0 / face.len() as f32;
+            let points: Vec<(&Vector3D, f32)> =
+                face.iter().map(|i| (&vertices[*i], weight)).collect();
+            weighted_sum(&points)
+        })
+        .collect();
+
+    let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    let mut vertex_faces: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+    for (face_index, face) in faces.iter().enumerate() {
+        for i in 0..face.len() {
+            let a = face[i];
+            let b = face[(i + 1) % face.len()];
+            edge_faces
+                .entry((a.min(b), a.max(b)))
+                .or_default()
+                .push(face_index);
+            vertex_faces[a].push(face_index);
+        }
+    }
+
+    // New vertices are laid out as: moved original vertices, edge points, face points
+    let mut new_vertices: Vec<Vector3D> = vertices.clone();
+    let mut edge_points: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut edge_midpoints: Vec<Vec<Vector3D>> = vec![vec![]; vertices.len()];
+    let mut border_neighbours: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+    let mut is_crease = vec![false; vertices.len()];
+
+    for (&(a, b), edge_face_indices) in &edge_faces {
+        let midpoint = weighted_sum(&[(&vertices[a], 0.5), (&vertices[b], 0.5)]);
+        edge_midpoints[a].push(midpoint);
+        edge_midpoints[b].push(midpoint);
+
+        let edge_point = match edge_face_indices.len() {
+            2 => weighted_sum(&[
+                (&vertices[a], 0.25),
+                (&vertices[b], 0.25),
+                (&face_points[edge_face_indices[0]], 0.25),
+                (&face_points[edge_face_indices[1]], 0.25),
+            ]),
+            1 => {
+                border_neighbours[a].push(b);
+                border_neighbours[b].push(a);
+                midpoint
+            }
+            _ => {
+                is_crease[a] = true;
+                is_crease[b] = true;
+                midpoint
+            }
+        };
+
+        new_vertices.push(edge_point);
+        edge_points.insert((a, b), new_vertices.len() - 1);
+    }
+
+    for (index, vertex) in vertices.iter().enumerate() {
+        if is_crease[index] || vertex_faces[index].is_empty() {
+            continue;
+        }
+
+        new_vertices[index] = match border_neighbours[index].len() {
+            0 => {
+                // (F + 2R + (n - 3)P) / n
+                let n = vertex_faces[index].len() as f32;
+                let face_weight = 1.0 / (n * n);
+                let edge_weight = 2.0 / (n * edge_midpoints[index].len() as f32);
+                let mut points = vec![(vertex, (n - 3.0) / n)];
+                points.extend(
+                    vertex_faces[index]
+                        .iter()
+                        .map(|face_index| (&face_points[*face_index], face_weight)),
+                );
+                points.extend(
+                    edge_midpoints[index]
+                        .iter()
+                        .map(|midpoint| (midpoint, edge_weight)),
+                );
+                weighted_sum(&points)
+            }
+            2 => weighted_sum(&[
+                (vertex, 3.0 / 4.0),
+                (&vertices[border_neighbours[index][0]], 1.0 / 8.0),
+                (&vertices[border_neighbours[index][1]], 1.0 / 8.0),
+            ]),
+            _ => *vertex,
+        };
+    }
+
+    let face_point_offset = new_vertices.len();
+    new_vertices.extend(face_points);
+
+    let edge_point = |a: usize, b: usize| edge_points[&(a.min(b), a.max(b))];
+    let mut polygons: Vec<Vec<Vector3D>> = vec![];
+    let mut triangles: Vec<Triangle> = vec![];
+    for (face_index, face) in faces.iter().enumerate() {
+        let face_point = face_point_offset + face_index;
+        for i in 0..face.len() {
+            let previous = face[(i + face.len() - 1) % face.len()];
+            let current = face[i];
+            let next = face[(i + 1) % face.len()];
+            let quad = [
+                current,
+                edge_point(current, next),
+                face_point,
+                edge_point(previous, current),
+            ];
+
+            triangles.push(Triangle::from_vectors(
+                &new_vertices,
+                [quad[0], quad[1], quad[2]],
+            ));
+            triangles.push(Triangle::from_vectors(
+                &new_vertices,
+                [quad[0], quad[2], quad[3]],
+            ));
+            polygons.push(quad.iter().map(|i| new_vertices[*i]).collect());
+        }
+    }
+
+    Mesh {
+        triangles,
+        polygons,
+    }
+}
+
+fn opposite_vertex(face: &[usize; 3], a: usize, b: usize) -> usize {
+    *face
+        .iter()
+        .find(|vertex| **vertex != a && **vertex != b)
+        .unwrap()
+}
+
+fn weighted_sum(points: &[(&Vector3D, f32)]) -> Vector3D {
+    let mut result = Vector3D::from_coords(0.0, 0.0, 0.0);
+    for (point, weight) in points {
+        result.x += point.x * weight;
+        result.y += point.y * weight;
+        result.z += point.z * weight;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_helpers::{cube, mesh_from_faces, tetrahedron};
+
+    fn has_vertex(mesh: &Mesh, expected: Vector3D) -> bool {
+        mesh.triangles.iter().any(|triangle| {
+            triangle
+                .vectors
+                .iter()
+                .any(|vertex| vertex.approx_eq(&expected, 1e-5))
+        })
+    }
+
+    // Two triangles with a single shared edge, everything else is border
+    fn open_square() -> Mesh {
+        let vertices = [
+            Vector3D::from_coords(0.0, 0.0, 0.0),
+            Vector3D::from_coords(1.0, 0.0, 0.0),
+            Vector3D::from_coords(1.0, 1.0, 0.0),
+            Vector3D::from_coords(0.0, 1.0, 0.0),
+        ];
+        mesh_from_faces(&vertices, &[&[0, 1, 2], &[0, 2, 3]])
+    }
+
+    #[test]
+    fn loop_splits_every_triangle_into_four() {
+        let mut mesh = tetrahedron();
+        for expected in [16, 64, 256] {
+            mesh = loop_subdivide(&mesh);
+            assert_eq!(mesh.triangles.len(), expected);
+            assert!(mesh.polygons.is_empty());
+        }
+    }
+
+    #[test]
+    fn loop_moves_tetrahedron_vertices_towards_the_center() {
+        let mesh = loop_subdivide(&tetrahedron());
+
+        // With beta 3/16 and the neighbours summing to -v, corners end up at v / 4
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.25, 0.25, 0.25)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(-0.25, -0.25, 0.25)));
+        // Edge points are 3/8 (a + b) + 1/8 (c + d) = 1/4 (a + b)
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.5, 0.0, 0.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.0, -0.5, 0.0)));
+        assert!(!has_vertex(&mesh, Vector3D::from_coords(1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn loop_keeps_open_borders_on_the_border_curve() {
+        let mesh = loop_subdivide(&open_square());
+
+        assert_eq!(mesh.triangles.len(), 8);
+        // Border edges are split in the middle
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.5, 0.0, 0.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.0, 0.5, 0.0)));
+        // Border vertices only see their two border neighbours
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.125, 0.125, 0.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.875, 0.125, 0.0)));
+        assert!(mesh
+            .triangles
+            .iter()
+            .all(|triangle| triangle.vectors.iter().all(|vertex| vertex.z == 0.0)));
+    }
+
+    #[test]
+    fn catmull_clark_makes_a_quad_for_every_face_corner() {
+        let mesh = catmull_clark_subdivide(&cube());
+        assert_eq!(mesh.polygons.len(), 24);
+        assert_eq!(mesh.triangles.len(), 48);
+        assert!(mesh.polygons.iter().all(|polygon| polygon.len() == 4));
+
+        let mesh = catmull_clark_subdivide(&mesh);
+        assert_eq!(mesh.polygons.len(), 96);
+        assert_eq!(mesh.triangles.len(), 192);
+    }
+
+    #[test]
+    fn catmull_clark_moves_cube_vertices_to_the_known_positions() {
+        let mesh = catmull_clark_subdivide(&cube());
+
+        // Face points are the face centers
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.0, 0.0, 1.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(-1.0, 0.0, 0.0)));
+        // Edge points average the edge ends and both face points
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.0, 0.75, 0.75)));
+        // Corners: (F + 2R + (n - 3)P) / n with F = 1/3 and R = 2/3 on every axis
+        let corner = 5.0 / 9.0;
+        assert!(has_vertex(
+            &mesh,
+            Vector3D::from_coords(corner, corner, corner)
+        ));
+        assert!(has_vertex(
+            &mesh,
+            Vector3D::from_coords(-corner, corner, -corner)
+        ));
+        assert!(!has_vertex(&mesh, Vector3D::from_coords(1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn catmull_clark_keeps_open_borders_on_the_border_curve() {
+        let vertices = [
+            Vector3D::from_coords(0.0, 0.0, 0.0),
+            Vector3D::from_coords(1.0, 0.0, 0.0),
+            Vector3D::from_coords(1.0, 1.0, 0.0),
+            Vector3D::from_coords(0.0, 1.0, 0.0),
+        ];
+        let mesh = catmull_clark_subdivide(&mesh_from_faces(&vertices, &[&[0, 1, 2, 3]]));
+
+        assert_eq!(mesh.polygons.len(), 4);
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.5, 0.5, 0.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.5, 0.0, 0.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(1.0, 0.5, 0.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.125, 0.125, 0.0)));
+        assert!(has_vertex(&mesh, Vector3D::from_coords(0.875, 0.875, 0.0)));
+    }
+}
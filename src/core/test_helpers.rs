@@ -0,0 +1,93 @@
+// Shared by the tests of the core modules
+use proptest::prelude::*;
+
+use super::{mesh::Mesh, triangle::Triangle, vector_3d::Vector3D};
+
+pub fn vector() -> impl Strategy<Value = Vector3D> {
+    (-100.0f32..100.0, -100.0f32..100.0, -100.0f32..100.0)
+        .prop_map(|(x, y, z)| Vector3D::from_coords(x, y, z))
+}
+
+// Triangle fans of the faces, faces with more than 3 vertices are kept as
+// polygons too like the obj loader does
+pub fn mesh_from_faces(vertices: &[Vector3D], faces: &[&[usize]]) -> Mesh {
+    let mut mesh = Mesh {
+        triangles: vec![],
+        polygons: vec![],
+    };
+    for face in faces {
+        for i in 1..face.len() - 1 {
+            mesh.triangles.push(Triangle::new_from_vectors([
+                vertices[face[0]],
+                vertices[face[i]],
+                vertices[face[i + 1]],
+            ]));
+        }
+    }
+    if faces.iter().any(|face| face.len() > 3) {
+        mesh.polygons = faces
+            .iter()
+            .map(|face| face.iter().map(|index| vertices[*index]).collect())
+            .collect();
+    }
+    mesh
+}
+
+// Closed and counter-clockwise seen from outside, centered on the origin
+pub fn tetrahedron() -> Mesh {
+    let vertices = [
+        Vector3D::from_coords(1.0, 1.0, 1.0),
+        Vector3D::from_coords(1.0, -1.0, -1.0),
+        Vector3D::from_coords(-1.0, 1.0, -1.0),
+        Vector3D::from_coords(-1.0, -1.0, 1.0),
+    ];
+    mesh_from_faces(&vertices, &[&[0, 1, 2], &[0, 3, 1], &[0, 2, 3], &[1, 3, 2]])
+}
+
+// Quads from -1 to 1 on every axis, counter-clockwise seen from outside
+pub fn cube() -> Mesh {
+    let vertices: Vec<Vector3D> = (0..8)
+        .map(|i| {
+            let coordinate = |bit: usize| if i & bit != 0 { 1.0 } else { -1.0 };
+            Vector3D::from_coords(coordinate(1), coordinate(2), coordinate(4))
+        })
+        .collect();
+    mesh_from_faces(
+        &vertices,
+        &[
+            &[0, 2, 3, 1],
+            &[4, 5, 7, 6],
+            &[0, 1, 5, 4],
+            &[2, 6, 7, 3],
+            &[0, 4, 6, 2],
+            &[1, 3, 7, 5],
+        ],
+    )
+}
+
+// Flat unit square in the xy plane split into `cells` x `cells` quads of two
+// triangles each, facing +z
+pub fn grid(cells: usize) -> Mesh {
+    let mut vertices = vec![];
+    for y in 0..=cells {
+        for x in 0..=cells {
+            vertices.push(Vector3D::from_coords(
+                x as f32 / cells as f32,
+                y as f32 / cells as f32,
+                0.0,
+            ));
+        }
+    }
+    let index = |x: usize, y: usize| y * (cells + 1) + x;
+    let faces: Vec<[usize; 3]> = (0..cells * cells)
+        .flat_map(|cell| {
+            let (x, y) = (cell % cells, cell / cells);
+            [
+                [index(x, y), index(x + 1, y), index(x + 1, y + 1)],
+                [index(x, y), index(x + 1, y + 1), index(x, y + 1)],
+            ]
+        })
+        .collect();
+    let faces: Vec<&[usize]> = faces.iter().map(|face| face.as_slice()).collect();
+    mesh_from_faces(&vertices, &faces)
+}
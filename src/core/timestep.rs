@@ -0,0 +1,56 @@
+// Turns variable frame times into a whole number of fixed update steps, so the
+// simulation does the same thing at any frame rate. The time that is left over
+// is used to interpolate between the last two steps when rendering.
+pub struct FixedTimestep {
+    // Seconds per update step
+    pub step: f32,
+    // Longer frames are cut off, so a stall doesn't cause a burst of updates
+    pub max_frame_time: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(updates_per_second: f32) -> FixedTimestep {
+        FixedTimestep {
+            step: 1.0 / updates_per_second,
+            max_frame_time: 0.25,
+            accumulator: 0.0,
+        }
+    }
+
+    // Adds the seconds since the previous frame, returns how many steps to run
+    pub fn advance(&mut self, frame_time: f32) -> usize {
+        self.accumulator += frame_time.clamp(0.0, self.max_frame_time);
+        let steps = (self.accumulator / self.step).floor();
+        self.accumulator -= steps * self.step;
+        steps as usize
+    }
+
+    // How far the current frame is from the last step to the next one, from 0 to 1
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn steps_do_not_depend_on_frame_rate(frames_per_second in 5u32..500) {
+            let mut timestep = FixedTimestep::new(60.0);
+            let frame_time = 1.0 / frames_per_second as f32;
+
+            let mut steps = 0;
+            for _ in 0..frames_per_second * 2 {
+                steps += timestep.advance(frame_time);
+                prop_assert!((0.0..=1.0).contains(&timestep.alpha()));
+            }
+
+            // Two seconds, give or take the rounding of the last frame
+            prop_assert!((119..=120).contains(&steps));
+        }
+    }
+}
@@ -2,7 +2,10 @@ use sdl2::pixels::Color;
 
 use crate::core::math::intersect_plane;
 
-use super::{math::vector_dot_product, vector_3d::Vector3D};
+use super::{
+    math::{vector_cross_product, vector_dot_product, vector_len},
+    vector_3d::Vector3D,
+};
 
 pub struct Triangle {
     pub vectors: [Vector3D; 3],
@@ -26,12 +29,18 @@ impl Triangle {
 
     pub fn from_vectors(vectors: &Vec<Vector3D>, indices: [usize; 3]) -> Triangle {
         Triangle::new_from_vectors([
-            vectors[indices[0]].clone(),
-            vectors[indices[1]].clone(),
-            vectors[indices[2]].clone(),
+            vectors[indices[0]],
+            vectors[indices[1]],
+            vectors[indices[2]],
         ])
     }
 
+    pub fn area(&self) -> f32 {
+        let line1 = self.vectors[1] - self.vectors[0];
+        let line2 = self.vectors[2] - self.vectors[0];
+        vector_len(&vector_cross_product(&line1, &line2)) * 0.5
+    }
+
     pub fn clip_against_plane(
         &self,
         plane_position: Vector3D,
@@ -82,7 +91,7 @@ impl Triangle {
 
                 let mut new_triangle = self.clone();
 
-                new_triangle.vectors[0] = self.vectors[inside].clone();
+                new_triangle.vectors[0] = self.vectors[inside];
                 new_triangle.vectors[1] = intersect_plane(
                     &plane_position,
                     &plane_normal,
@@ -108,8 +117,8 @@ impl Triangle {
                 let mut new_triangle2 = self.clone();
 
                 // First triangle
-                new_triangle1.vectors[0] = self.vectors[inside1].clone();
-                new_triangle1.vectors[1] = self.vectors[inside2].clone();
+                new_triangle1.vectors[0] = self.vectors[inside1];
+                new_triangle1.vectors[1] = self.vectors[inside2];
                 new_triangle1.vectors[2] = intersect_plane(
                     &plane_position,
                     &plane_normal,
@@ -118,8 +127,8 @@ impl Triangle {
                 );
 
                 // Second triangle
-                new_triangle2.vectors[0] = self.vectors[inside2].clone();
-                new_triangle2.vectors[1] = new_triangle1.vectors[2].clone();
+                new_triangle2.vectors[0] = self.vectors[inside2];
+                new_triangle2.vectors[1] = new_triangle1.vectors[2];
                 new_triangle2.vectors[2] = intersect_plane(
                     &plane_position,
                     &plane_normal,
@@ -137,8 +146,100 @@ impl Triangle {
 impl Clone for Triangle {
     fn clone(&self) -> Triangle {
         Triangle {
-            vectors: self.vectors.clone(),
-            base_color: self.base_color.clone(),
+            vectors: self.vectors,
+            base_color: self.base_color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_helpers::vector;
+    use proptest::prelude::*;
+
+    fn triangle(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> Triangle {
+        Triangle::new_from_vectors([
+            Vector3D::from_coords(a.0, a.1, 0.0),
+            Vector3D::from_coords(b.0, b.1, 0.0),
+            Vector3D::from_coords(c.0, c.1, 0.0),
+        ])
+    }
+
+    fn clip_x(triangle: &Triangle, x: f32) -> Vec<Triangle> {
+        triangle.clip_against_plane(
+            Vector3D::from_coords(x, 0.0, 0.0),
+            Vector3D::from_coords(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn clip_fully_inside() {
+        let clipped = clip_x(&triangle((1.0, 0.0), (2.0, 0.0), (1.0, 1.0)), 0.0);
+        assert_eq!(clipped.len(), 1);
+        assert_eq!(
+            clipped[0].vectors,
+            triangle((1.0, 0.0), (2.0, 0.0), (1.0, 1.0)).vectors
+        );
+    }
+
+    #[test]
+    fn clip_fully_outside() {
+        assert!(clip_x(&triangle((-1.0, 0.0), (-2.0, 0.0), (-1.0, 1.0)), 0.0).is_empty());
+    }
+
+    #[test]
+    fn clip_one_inside() {
+        let clipped = clip_x(&triangle((2.0, 0.0), (-2.0, 0.0), (-2.0, 4.0)), 0.0);
+        assert_eq!(clipped.len(), 1);
+        // The corner at x = 2 keeps the part up to x = 0, a quarter of the triangle
+        assert!((clipped[0].area() - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn clip_two_inside() {
+        let clipped = clip_x(&triangle((2.0, 0.0), (2.0, 4.0), (-2.0, 0.0)), 0.0);
+        assert_eq!(clipped.len(), 2);
+        let area: f32 = clipped.iter().map(Triangle::area).sum();
+        assert!((area - 6.0).abs() < 1e-5);
+    }
+
+    proptest! {
+        #[test]
+        fn clipping_conserves_area(
+            a in vector(),
+            b in vector(),
+            c in vector(),
+            plane_position in vector(),
+            plane_normal in vector(),
+        ) {
+            prop_assume!(vector_len(&plane_normal) > 0.1);
+            let triangle = Triangle::new_from_vectors([a, b, c]);
+
+            // The parts on both sides of the plane add up to the whole triangle
+            let inside = triangle.clip_against_plane(plane_position, plane_normal);
+            let outside = triangle.clip_against_plane(plane_position, -plane_normal);
+            let area: f32 = inside.iter().chain(&outside).map(Triangle::area).sum();
+            prop_assert!((area - triangle.area()).abs() <= 1e-3 * triangle.area().max(1.0));
+        }
+
+        #[test]
+        fn clipped_triangles_are_inside(
+            a in vector(),
+            b in vector(),
+            c in vector(),
+            plane_position in vector(),
+            plane_normal in vector(),
+        ) {
+            prop_assume!(vector_len(&plane_normal) > 0.1);
+            let normal = plane_normal.from_normalise();
+            let triangle = Triangle::new_from_vectors([a, b, c]);
+
+            for clipped in triangle.clip_against_plane(plane_position, plane_normal) {
+                for vector in &clipped.vectors {
+                    prop_assert!(vector_dot_product(&normal, &(vector - plane_position)) >= -1e-2);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,183 @@
+use std::fmt;
+
+use super::{
+    indexed_mesh::{face_has_directed_edge, IndexedMesh},
+    mesh::Mesh,
+};
+
+// Vertices closer together than this are considered to be the same vertex
+pub const DEFAULT_WELD_DISTANCE: f32 = 0.00001;
+
+// Triangles with a smaller area than this have no usable normal
+pub const MIN_TRIANGLE_AREA: f32 = 1e-10;
+
+pub struct ValidationReport {
+    pub triangle_count: usize,
+    pub vertex_count: usize,
+    pub degenerate_triangles: usize,
+    pub zero_area_triangles: usize,
+    pub duplicate_vertices: usize,
+    pub non_manifold_edges: usize,
+    pub boundary_edges: usize,
+    pub inconsistent_edges: usize,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.degenerate_triangles == 0
+            && self.zero_area_triangles == 0
+            && self.duplicate_vertices == 0
+            && self.non_manifold_edges == 0
+            && self.inconsistent_edges == 0
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Mesh validation report")?;
+        writeln!(f, "  Triangles:            {}", self.triangle_count)?;
+        writeln!(f, "  Unique vertices:      {}", self.vertex_count)?;
+        writeln!(f, "  Degenerate triangles: {}", self.degenerate_triangles)?;
+        writeln!(f, "  Zero-area triangles:  {}", self.zero_area_triangles)?;
+        writeln!(f, "  Duplicate vertices:   {}", self.duplicate_vertices)?;
+        writeln!(f, "  Non-manifold edges:   {}", self.non_manifold_edges)?;
+        writeln!(f, "  Open boundary edges:  {}", self.boundary_edges)?;
+        writeln!(f, "  Inconsistent winding: {}", self.inconsistent_edges)?;
+        if self.is_valid() {
+            write!(f, "Result: OK")
+        } else {
+            write!(f, "Result: problems found")
+        }
+    }
+}
+
+pub fn validate_mesh(mesh: &Mesh, weld_distance: f32) -> ValidationReport {
+    let exact = IndexedMesh::from_mesh(mesh, 0.0);
+    let welded = IndexedMesh::from_mesh(mesh, weld_distance);
+
+    // A degenerate triangle uses the same vertex more than once, a zero-area
+    // triangle has three distinct vertices that are collinear.
+    let mut degenerate_triangles = 0;
+    let mut zero_area_triangles = 0;
+    for (face, triangle) in welded.faces.iter().zip(&mesh.triangles) {
+        if face[0] == face[1] || face[1] == face[2] || face[2] == face[0] {
+            degenerate_triangles += 1;
+        } else if triangle.area() < MIN_TRIANGLE_AREA {
+            zero_area_triangles += 1;
+        }
+    }
+
+    let mut non_manifold_edges = 0;
+    let mut boundary_edges = 0;
+    let mut inconsistent_edges = 0;
+    for ((a, b), faces) in welded.edge_faces() {
+        match faces.len() {
+            1 => boundary_edges += 1,
+            2 => {
+                // Neighbouring faces must walk their shared edge in opposite directions
+                let first = &welded.faces[faces[0]];
+                let second = &welded.faces[faces[1]];
+                if face_has_directed_edge(first, a, b) == face_has_directed_edge(second, a, b) {
+                    inconsistent_edges += 1;
+                }
+            }
+            _ => non_manifold_edges += 1,
+        }
+    }
+
+    ValidationReport {
+        triangle_count: mesh.triangles.len(),
+        vertex_count: welded.vertices.len(),
+        degenerate_triangles,
+        zero_area_triangles,
+        duplicate_vertices: exact.vertices.len() - welded.vertices.len(),
+        non_manifold_edges,
+        boundary_edges,
+        inconsistent_edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        test_helpers::{cube, grid, tetrahedron},
+        triangle::Triangle,
+        vector_3d::Vector3D,
+    };
+
+    #[test]
+    fn closed_meshes_are_valid() {
+        for mesh in [tetrahedron(), cube()] {
+            let report = validate_mesh(&mesh, DEFAULT_WELD_DISTANCE);
+            assert!(report.is_valid());
+            assert_eq!(report.boundary_edges, 0);
+            assert!(report.to_string().ends_with("Result: OK"));
+        }
+
+        let report = validate_mesh(&cube(), DEFAULT_WELD_DISTANCE);
+        assert_eq!(report.triangle_count, 12);
+        assert_eq!(report.vertex_count, 8);
+    }
+
+    #[test]
+    fn open_borders_are_reported_but_valid() {
+        let report = validate_mesh(&grid(2), DEFAULT_WELD_DISTANCE);
+        assert!(report.is_valid());
+        assert_eq!(report.boundary_edges, 8);
+    }
+
+    #[test]
+    fn finds_degenerate_and_zero_area_triangles() {
+        let mut mesh = tetrahedron();
+        let a = Vector3D::from_coords(5.0, 0.0, 0.0);
+        // Two corners that weld together
+        let nearly_a = Vector3D::from_coords(5.0, 0.0, DEFAULT_WELD_DISTANCE * 0.5);
+        mesh.triangles.push(Triangle::new_from_vectors([
+            a,
+            nearly_a,
+            Vector3D::from_coords(6.0, 1.0, 0.0),
+        ]));
+        // Three distinct corners on a line
+        mesh.triangles.push(Triangle::new_from_vectors([
+            Vector3D::from_coords(8.0, 0.0, 0.0),
+            Vector3D::from_coords(9.0, 1.0, 0.0),
+            Vector3D::from_coords(10.0, 2.0, 0.0),
+        ]));
+
+        let report = validate_mesh(&mesh, DEFAULT_WELD_DISTANCE);
+        assert_eq!(report.degenerate_triangles, 1);
+        assert_eq!(report.zero_area_triangles, 1);
+        assert_eq!(report.duplicate_vertices, 1);
+        assert!(!report.is_valid());
+        assert!(report.to_string().ends_with("Result: problems found"));
+    }
+
+    #[test]
+    fn finds_flipped_triangles() {
+        let mut mesh = tetrahedron();
+        mesh.triangles[0].vectors.swap(1, 2);
+
+        // Every edge of the flipped triangle runs the same way as its neighbour's
+        let report = validate_mesh(&mesh, DEFAULT_WELD_DISTANCE);
+        assert_eq!(report.inconsistent_edges, 3);
+        assert_eq!(report.non_manifold_edges, 0);
+    }
+
+    #[test]
+    fn finds_non_manifold_edges() {
+        let mut mesh = tetrahedron();
+        // A fin on the edge between the first two corners
+        let [v0, v1, _] = mesh.triangles[0].vectors;
+        mesh.triangles.push(Triangle::new_from_vectors([
+            v1,
+            v0,
+            Vector3D::from_coords(3.0, 3.0, 3.0),
+        ]));
+
+        let report = validate_mesh(&mesh, DEFAULT_WELD_DISTANCE);
+        assert_eq!(report.non_manifold_edges, 1);
+        assert_eq!(report.boundary_edges, 2);
+        assert!(!report.is_valid());
+    }
+}
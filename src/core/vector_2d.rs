@@ -0,0 +1,50 @@
+use std::fmt;
+
+use super::vector_ops::impl_vector_ops;
+
+// Screen positions and texture coordinates
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Vector2D {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vector2D {
+    pub fn new() -> Self {
+        Self { x: 0.0, y: 0.0 }
+    }
+
+    pub fn from_coords(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn dot(&self, other: &Vector2D) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    // Linear interpolation, `t` goes from 0.0 (self) to 1.0 (other)
+    pub fn lerp(&self, other: &Vector2D, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    // Equality that allows for floating point error in every component
+    pub fn approx_eq(&self, other: &Vector2D, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon && (self.y - other.y).abs() <= epsilon
+    }
+}
+
+impl fmt::Display for Vector2D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        fmt::Display::fmt(&self.x, f)?;
+        write!(f, ", ")?;
+        fmt::Display::fmt(&self.y, f)?;
+        write!(f, ")")
+    }
+}
+
+impl_vector_ops!(Vector2D, x, y);
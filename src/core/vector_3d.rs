@@ -1,12 +1,15 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use super::math::vector_len;
+use super::{
+    math::{vector_dot_product, vector_len},
+    vector_ops::impl_vector_ops,
+};
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Vector3D {
     pub x: f32,
     pub y: f32,
     pub z: f32,
-    pub w: f32,
 }
 
 impl Vector3D {
@@ -15,18 +18,34 @@ impl Vector3D {
             x: 0.0,
             y: 0.0,
             z: 0.0,
-            w: 1.0,
         }
     }
 
     pub fn from_coords(x: f32, y: f32, z: f32) -> Self {
-        Self { x, y, z, w: 1.0 }
+        Self { x, y, z }
     }
 
     pub fn from_normalise(&self) -> Self {
-        let length = vector_len(&self);
+        let length = vector_len(self);
         Self::from_coords(self.x / length, self.y / length, self.z / length)
     }
+
+    // Linear interpolation, `t` goes from 0.0 (self) to 1.0 (other)
+    pub fn lerp(&self, other: &Vector3D, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    // Mirrors the vector around a (normalised) surface normal
+    pub fn reflect(&self, normal: &Vector3D) -> Self {
+        self - normal * (2.0 * vector_dot_product(self, normal))
+    }
+
+    // Equality that allows for floating point error in every component
+    pub fn approx_eq(&self, other: &Vector3D, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon
+            && (self.z - other.z).abs() <= epsilon
+    }
 }
 
 impl FromStr for Vector3D {
@@ -44,45 +63,17 @@ impl FromStr for Vector3D {
     }
 }
 
-impl std::ops::Add for &Vector3D {
-    type Output = Vector3D;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Vector3D::from_coords(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
-    }
-}
-
-impl std::ops::Sub for &Vector3D {
-    type Output = Vector3D;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Vector3D::from_coords(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+impl fmt::Display for Vector3D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Pass the formatter on, so precision like `{:.2}` applies to every component
+        write!(f, "(")?;
+        fmt::Display::fmt(&self.x, f)?;
+        write!(f, ", ")?;
+        fmt::Display::fmt(&self.y, f)?;
+        write!(f, ", ")?;
+        fmt::Display::fmt(&self.z, f)?;
+        write!(f, ")")
     }
 }
 
-impl std::ops::Div<f32> for &Vector3D {
-    type Output = Vector3D;
-
-    fn div(self, rhs: f32) -> Self::Output {
-        Vector3D::from_coords(self.x / rhs, self.y / rhs, self.z / rhs)
-    }
-}
-
-impl std::ops::Mul<f32> for &Vector3D {
-    type Output = Vector3D;
-
-    fn mul(self, rhs: f32) -> Self::Output {
-        Vector3D::from_coords(self.x * rhs, self.y * rhs, self.z * rhs)
-    }
-}
-
-impl Clone for Vector3D {
-    fn clone(&self) -> Self {
-        Self {
-            x: self.x.clone(),
-            y: self.y.clone(),
-            z: self.z.clone(),
-            w: self.w.clone(),
-        }
-    }
-}
+impl_vector_ops!(Vector3D, x, y, z);
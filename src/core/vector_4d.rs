@@ -0,0 +1,61 @@
+use std::fmt;
+
+use super::{vector_3d::Vector3D, vector_ops::impl_vector_ops};
+
+// Homogeneous coordinates, for projecting and unprojecting points.
+// Points have `w` set to 1.0, directions have `w` set to 0.0.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Vector4D {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Vector4D {
+    pub fn from_coords(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub fn from_point(point: &Vector3D) -> Self {
+        Self::from_coords(point.x, point.y, point.z, 1.0)
+    }
+
+    pub fn from_direction(direction: &Vector3D) -> Self {
+        Self::from_coords(direction.x, direction.y, direction.z, 0.0)
+    }
+
+    // Drops `w` without dividing by it
+    pub fn xyz(&self) -> Vector3D {
+        Vector3D::from_coords(self.x, self.y, self.z)
+    }
+
+    // Divides by `w` to get back to 3D
+    pub fn perspective_divide(&self) -> Vector3D {
+        Vector3D::from_coords(self.x / self.w, self.y / self.w, self.z / self.w)
+    }
+
+    // Equality that allows for floating point error in every component
+    pub fn approx_eq(&self, other: &Vector4D, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon
+            && (self.z - other.z).abs() <= epsilon
+            && (self.w - other.w).abs() <= epsilon
+    }
+}
+
+impl fmt::Display for Vector4D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        fmt::Display::fmt(&self.x, f)?;
+        write!(f, ", ")?;
+        fmt::Display::fmt(&self.y, f)?;
+        write!(f, ", ")?;
+        fmt::Display::fmt(&self.z, f)?;
+        write!(f, ", ")?;
+        fmt::Display::fmt(&self.w, f)?;
+        write!(f, ")")
+    }
+}
+
+impl_vector_ops!(Vector4D, x, y, z, w);
@@ -0,0 +1,120 @@
+// Arithmetic operators shared by the vector types. Every operator works on both
+// owned values and references, so `a + b` and `&a + &b` can be mixed freely.
+macro_rules! impl_vector_ops {
+    ($vector:ident, $($field:ident),+) => {
+        impl_vector_ops!(@binary $vector, Add, add, +, $($field),+);
+        impl_vector_ops!(@binary $vector, Sub, sub, -, $($field),+);
+        // Component-wise multiplication
+        impl_vector_ops!(@binary $vector, Mul, mul, *, $($field),+);
+
+        impl_vector_ops!(@scalar $vector, Mul, mul, *, $($field),+);
+        impl_vector_ops!(@scalar $vector, Div, div, /, $($field),+);
+
+        impl_vector_ops!(@assign $vector, AddAssign, add_assign, +=, $($field),+);
+        impl_vector_ops!(@assign $vector, SubAssign, sub_assign, -=, $($field),+);
+
+        impl std::ops::MulAssign<f32> for $vector {
+            fn mul_assign(&mut self, rhs: f32) {
+                $(self.$field *= rhs;)+
+            }
+        }
+
+        impl std::ops::DivAssign<f32> for $vector {
+            fn div_assign(&mut self, rhs: f32) {
+                $(self.$field /= rhs;)+
+            }
+        }
+
+        impl std::ops::Neg for $vector {
+            type Output = $vector;
+
+            fn neg(self) -> Self::Output {
+                $vector { $($field: -self.$field),+ }
+            }
+        }
+
+        impl std::ops::Neg for &$vector {
+            type Output = $vector;
+
+            fn neg(self) -> Self::Output {
+                -*self
+            }
+        }
+
+        impl std::ops::Mul<$vector> for f32 {
+            type Output = $vector;
+
+            fn mul(self, rhs: $vector) -> Self::Output {
+                rhs * self
+            }
+        }
+    };
+
+    (@binary $vector:ident, $trait:ident, $method:ident, $op:tt, $($field:ident),+) => {
+        impl std::ops::$trait for $vector {
+            type Output = $vector;
+
+            fn $method(self, rhs: $vector) -> Self::Output {
+                $vector { $($field: self.$field $op rhs.$field),+ }
+            }
+        }
+
+        impl std::ops::$trait for &$vector {
+            type Output = $vector;
+
+            fn $method(self, rhs: &$vector) -> Self::Output {
+                *self $op *rhs
+            }
+        }
+
+        impl std::ops::$trait<&$vector> for $vector {
+            type Output = $vector;
+
+            fn $method(self, rhs: &$vector) -> Self::Output {
+                self $op *rhs
+            }
+        }
+
+        impl std::ops::$trait<$vector> for &$vector {
+            type Output = $vector;
+
+            fn $method(self, rhs: $vector) -> Self::Output {
+                *self $op rhs
+            }
+        }
+    };
+
+    (@scalar $vector:ident, $trait:ident, $method:ident, $op:tt, $($field:ident),+) => {
+        impl std::ops::$trait<f32> for $vector {
+            type Output = $vector;
+
+            fn $method(self, rhs: f32) -> Self::Output {
+                $vector { $($field: self.$field $op rhs),+ }
+            }
+        }
+
+        impl std::ops::$trait<f32> for &$vector {
+            type Output = $vector;
+
+            fn $method(self, rhs: f32) -> Self::Output {
+                *self $op rhs
+            }
+        }
+    };
+
+    (@assign $vector:ident, $trait:ident, $method:ident, $op:tt, $($field:ident),+) => {
+        impl std::ops::$trait for $vector {
+            fn $method(&mut self, rhs: $vector) {
+                $(self.$field $op rhs.$field;)+
+            }
+        }
+
+        impl std::ops::$trait<&$vector> for $vector {
+            fn $method(&mut self, rhs: &$vector) {
+                $(self.$field $op rhs.$field;)+
+            }
+        }
+    };
+}
+
+pub(crate) use impl_vector_ops;
@@ -0,0 +1,362 @@
+use std::ops::Range;
+
+use super::{matrix_4x4::Matrix4X4, mesh::Mesh, vector_3d::Vector3D, vector_4d::Vector4D};
+
+// Vertices stored as a structure of arrays, one array per component, so many
+// vertices can be transformed at once with SIMD instructions.
+pub struct VertexBatch {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub z: Vec<f32>,
+    pub w: Vec<f32>,
+}
+
+impl VertexBatch {
+    pub fn new() -> VertexBatch {
+        VertexBatch {
+            x: vec![],
+            y: vec![],
+            z: vec![],
+            w: vec![],
+        }
+    }
+
+    // Points, so `w` is 1.0 for every vertex
+    pub fn from_vectors(vectors: &[Vector3D]) -> VertexBatch {
+        VertexBatch {
+            x: vectors.iter().map(|vector| vector.x).collect(),
+            y: vectors.iter().map(|vector| vector.y).collect(),
+            z: vectors.iter().map(|vector| vector.z).collect(),
+            w: vec![1.0; vectors.len()],
+        }
+    }
+
+    // Three vertices per triangle, triangle `i` starts at vertex `3 * i`
+    pub fn from_mesh(mesh: &Mesh) -> VertexBatch {
+        let vectors: Vec<Vector3D> = mesh
+            .triangles
+            .iter()
+            .flat_map(|triangle| triangle.vectors)
+            .collect();
+        VertexBatch::from_vectors(&vectors)
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Vector4D {
+        Vector4D::from_coords(self.x[index], self.y[index], self.z[index], self.w[index])
+    }
+
+    // Drops `w`, for batches transformed by affine matrices
+    pub fn point(&self, index: usize) -> Vector3D {
+        Vector3D::from_coords(self.x[index], self.y[index], self.z[index])
+    }
+
+    pub fn transform(&self, matrix: &Matrix4X4) -> VertexBatch {
+        let mut output = VertexBatch::new();
+        self.transform_into(matrix, &mut output);
+        output
+    }
+
+    // Transforms every vertex, reusing the memory of `output`. Uses the widest
+    // SIMD instructions the CPU supports.
+    pub fn transform_into(&self, matrix: &Matrix4X4, output: &mut VertexBatch) {
+        output.resize(self.len());
+        transform_ranges(self, matrix, output, std::iter::once(0..self.len()));
+    }
+
+    // Like `transform_into`, but only the vertices of `triangles` are transformed,
+    // the rest of `output` is left as it was. Triangles that follow each other are
+    // done in one go, so a sorted list is fastest. Panics on a triangle that is not
+    // in the batch.
+    pub fn transform_triangles_into(
+        &self,
+        matrix: &Matrix4X4,
+        triangles: &[usize],
+        output: &mut VertexBatch,
+    ) {
+        output.resize(self.len());
+        let mut triangles = triangles.iter().peekable();
+        let ranges = std::iter::from_fn(|| {
+            let first = *triangles.next()?;
+            let mut last = first;
+            while let Some(&&next) = triangles.peek() {
+                if next != last + 1 {
+                    break;
+                }
+                last = next;
+                triangles.next();
+            }
+            Some(first * 3..last * 3 + 3)
+        });
+        transform_ranges(self, matrix, output, ranges);
+    }
+
+    // One vertex at a time, for comparing against the SIMD versions
+    pub fn transform_scalar_into(&self, matrix: &Matrix4X4, output: &mut VertexBatch) {
+        output.resize(self.len());
+        transform_scalar(self, matrix, output, 0..self.len());
+    }
+
+    fn resize(&mut self, len: usize) {
+        self.x.resize(len, 0.0);
+        self.y.resize(len, 0.0);
+        self.z.resize(len, 0.0);
+        self.w.resize(len, 0.0);
+    }
+}
+
+// Picks the SIMD version once for all ranges. The SIMD versions don't check
+// bounds, so every range is checked against all the component arrays first.
+fn transform_ranges(
+    input: &VertexBatch,
+    matrix: &Matrix4X4,
+    output: &mut VertexBatch,
+    ranges: impl IntoIterator<Item = Range<usize>>,
+) {
+    let len = [
+        &input.x, &input.y, &input.z, &input.w, &output.x, &output.y, &output.z, &output.w,
+    ]
+    .iter()
+    .map(|component| component.len())
+    .min()
+    .unwrap_or(0);
+    let ranges = ranges.into_iter().inspect(|range| {
+        assert!(
+            range.start <= range.end && range.end <= len,
+            "vertices {:?} are outside a batch of {} vertices",
+            range,
+            len
+        );
+    });
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx") {
+            for range in ranges {
+                // SAFETY: the CPU supports AVX, checked above
+                unsafe { transform_avx(input, matrix, output, range) };
+            }
+        } else {
+            for range in ranges {
+                // SAFETY: SSE is part of every x86_64 CPU
+                unsafe { transform_sse(input, matrix, output, range) };
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    for range in ranges {
+        transform_scalar(input, matrix, output, range);
+    }
+}
+
+fn transform_scalar(
+    input: &VertexBatch,
+    matrix: &Matrix4X4,
+    output: &mut VertexBatch,
+    range: Range<usize>,
+) {
+    let m = &matrix.content;
+
+    for i in range {
+        let (x, y, z, w) = (input.x[i], input.y[i], input.z[i], input.w[i]);
+        output.x[i] = x * m[0][0] + y * m[1][0] + z * m[2][0] + w * m[3][0];
+        output.y[i] = x * m[0][1] + y * m[1][1] + z * m[2][1] + w * m[3][1];
+        output.z[i] = x * m[0][2] + y * m[1][2] + z * m[2][2] + w * m[3][2];
+        output.w[i] = x * m[0][3] + y * m[1][3] + z * m[2][3] + w * m[3][3];
+    }
+}
+
+// Both SIMD versions work the same way: every matrix element is broadcast to all
+// lanes, then each lane computes one output component for a different vertex.
+// Vertices at the end of the range that don't fill up a whole register are done
+// by `transform_scalar`.
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse")]
+unsafe fn transform_sse(
+    input: &VertexBatch,
+    matrix: &Matrix4X4,
+    output: &mut VertexBatch,
+    range: Range<usize>,
+) {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 4;
+    let m = &matrix.content;
+    let simd_end = range.start + range.len() / LANES * LANES;
+
+    for i in (range.start..simd_end).step_by(LANES) {
+        let x = _mm_loadu_ps(input.x.as_ptr().add(i));
+        let y = _mm_loadu_ps(input.y.as_ptr().add(i));
+        let z = _mm_loadu_ps(input.z.as_ptr().add(i));
+        let w = _mm_loadu_ps(input.w.as_ptr().add(i));
+
+        let outputs = [
+            output.x.as_mut_ptr(),
+            output.y.as_mut_ptr(),
+            output.z.as_mut_ptr(),
+            output.w.as_mut_ptr(),
+        ];
+        for (column, destination) in outputs.into_iter().enumerate() {
+            let result = _mm_add_ps(
+                _mm_add_ps(
+                    _mm_mul_ps(x, _mm_set1_ps(m[0][column])),
+                    _mm_mul_ps(y, _mm_set1_ps(m[1][column])),
+                ),
+                _mm_add_ps(
+                    _mm_mul_ps(z, _mm_set1_ps(m[2][column])),
+                    _mm_mul_ps(w, _mm_set1_ps(m[3][column])),
+                ),
+            );
+            _mm_storeu_ps(destination.add(i), result);
+        }
+    }
+
+    transform_scalar(input, matrix, output, simd_end..range.end);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn transform_avx(
+    input: &VertexBatch,
+    matrix: &Matrix4X4,
+    output: &mut VertexBatch,
+    range: Range<usize>,
+) {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 8;
+    let m = &matrix.content;
+    let simd_end = range.start + range.len() / LANES * LANES;
+
+    for i in (range.start..simd_end).step_by(LANES) {
+        let x = _mm256_loadu_ps(input.x.as_ptr().add(i));
+        let y = _mm256_loadu_ps(input.y.as_ptr().add(i));
+        let z = _mm256_loadu_ps(input.z.as_ptr().add(i));
+        let w = _mm256_loadu_ps(input.w.as_ptr().add(i));
+
+        let outputs = [
+            output.x.as_mut_ptr(),
+            output.y.as_mut_ptr(),
+            output.z.as_mut_ptr(),
+            output.w.as_mut_ptr(),
+        ];
+        for (column, destination) in outputs.into_iter().enumerate() {
+            let result = _mm256_add_ps(
+                _mm256_add_ps(
+                    _mm256_mul_ps(x, _mm256_set1_ps(m[0][column])),
+                    _mm256_mul_ps(y, _mm256_set1_ps(m[1][column])),
+                ),
+                _mm256_add_ps(
+                    _mm256_mul_ps(z, _mm256_set1_ps(m[2][column])),
+                    _mm256_mul_ps(w, _mm256_set1_ps(m[3][column])),
+                ),
+            );
+            _mm256_storeu_ps(destination.add(i), result);
+        }
+    }
+
+    transform_scalar(input, matrix, output, simd_end..range.end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn matrix() -> Matrix4X4 {
+        let rotation = &Matrix4X4::from_rotation_x(0.3) * &Matrix4X4::from_rotation_y(-1.1);
+        let world = &rotation * &Matrix4X4::from_translation(1.0, -2.0, 5.0);
+        &world * &Matrix4X4::from_projection(90.0, 0.5625, 0.1, 1000.0)
+    }
+
+    #[test]
+    fn empty_batch() {
+        let output = VertexBatch::new().transform(&matrix());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn matches_single_vertex_transform() {
+        let vectors: Vec<Vector3D> = (0..37)
+            .map(|i| Vector3D::from_coords(i as f32, (i * 7 % 5) as f32, -(i as f32) * 0.5))
+            .collect();
+        let matrix = matrix();
+        let output = VertexBatch::from_vectors(&vectors).transform(&matrix);
+
+        for (i, vector) in vectors.iter().enumerate() {
+            let expected = &matrix * &Vector4D::from_point(vector);
+            assert!(output.get(i).approx_eq(&expected, 1e-4));
+        }
+    }
+
+    #[test]
+    fn transforms_only_the_given_triangles() {
+        let vectors: Vec<Vector3D> = (0..36)
+            .map(|i| Vector3D::from_coords(i as f32, 1.0, -2.0))
+            .collect();
+        let batch = VertexBatch::from_vectors(&vectors);
+        let matrix = matrix();
+        let all = batch.transform(&matrix);
+
+        let triangles = [1, 2, 3, 7, 11];
+        let mut output = VertexBatch::new();
+        batch.transform_triangles_into(&matrix, &triangles, &mut output);
+        for i in 0..batch.len() {
+            if triangles.contains(&(i / 3)) {
+                assert!(output.get(i).approx_eq(&all.get(i), 1e-4));
+            } else {
+                assert_eq!(output.get(i), Vector4D::from_coords(0.0, 0.0, 0.0, 0.0));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "outside a batch of 36 vertices")]
+    fn triangles_outside_the_batch_panic() {
+        let vectors = vec![Vector3D::from_coords(1.0, 2.0, 3.0); 36];
+        let batch = VertexBatch::from_vectors(&vectors);
+        batch.transform_triangles_into(&matrix(), &[100], &mut VertexBatch::new());
+    }
+
+    #[test]
+    fn repeated_triangles_are_not_every_triangle() {
+        let vectors: Vec<Vector3D> = (0..6)
+            .map(|i| Vector3D::from_coords(i as f32, 0.0, 1.0))
+            .collect();
+        let batch = VertexBatch::from_vectors(&vectors);
+        let mut output = VertexBatch::new();
+        batch.transform_triangles_into(&matrix(), &[0, 0], &mut output);
+        assert_eq!(output.get(3), Vector4D::from_coords(0.0, 0.0, 0.0, 0.0));
+    }
+
+    proptest! {
+        #[test]
+        fn simd_matches_scalar(
+            coords in prop::collection::vec((-100.0f32..100.0, -100.0f32..100.0, -100.0f32..100.0), 0..100),
+        ) {
+            let vectors: Vec<Vector3D> =
+                coords.iter().map(|(x, y, z)| Vector3D::from_coords(*x, *y, *z)).collect();
+            let batch = VertexBatch::from_vectors(&vectors);
+            let matrix = matrix();
+
+            let mut simd = VertexBatch::new();
+            let mut scalar = VertexBatch::new();
+            batch.transform_into(&matrix, &mut simd);
+            batch.transform_scalar_into(&matrix, &mut scalar);
+
+            prop_assert_eq!(simd.len(), vectors.len());
+            for i in 0..vectors.len() {
+                prop_assert!(simd.get(i).approx_eq(&scalar.get(i), 1e-3));
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+// Types are built with `new()`, only the ones without it implement `Default`
+#![allow(clippy::new_without_default)]
+
+pub mod core;
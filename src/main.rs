@@ -1,123 +1,197 @@
-use clap::{Arg, ArgMatches, Command};
-use core::engine::Engine;
-use sdl2::event::{Event, WindowEvent};
-use sdl2::keyboard::Keycode;
-use std::collections::HashSet;
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+mod benchmark;
+mod viewer;
 
-mod core;
+use benchmark::run_benchmark;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use driedee_rs::core::{
+    camera::Camera,
+    engine::Engine,
+    font::Font,
+    input::InputMap,
+    math::vector_len,
+    matrix_4x4::Matrix4X4,
+    mesh::Mesh,
+    renderer::{CullMode, RenderMode},
+    validation::{validate_mesh, DEFAULT_WELD_DISTANCE},
+    vector_3d::Vector3D,
+};
+use viewer::Viewer;
 
 fn main() -> Result<(), String> {
     let matches = set_commands();
 
-    let object_to_load = matches
-        .get_one::<String>("object")
-        .cloned()
-        .unwrap_or(String::from("./teapot.obj"));
-
-    let mut engine: Engine = Engine::new("3D Engine", 1280, 720, object_to_load.as_str());
-
-    // Create the SDL event pump to handle events
-    let mut event_pump = engine
-        .sdl_context
-        .event_pump()
-        .expect("Error creating event pump");
-
-    // Set the target frame rate (FPS)
-    let target_fps = matches.get_one::<f32>("max-fps").cloned().unwrap_or(60.0);
-    let target_frame_duration = Duration::from_secs_f32(1.0 / target_fps);
-
-    let mut pressed_keys: HashSet<Keycode> = HashSet::new();
-    let sensitivity = 0.2;
-    let mouse_util = engine.sdl_context.mouse();
-    mouse_util.set_relative_mouse_mode(true);
-    mouse_util.show_cursor(false);
-
-    // Main loop
-    let mut running = true;
-    let mut last_frame_time = Instant::now();
-    let mut last_frame_duration = 0.016;
-    while running {
-        let now = Instant::now();
-        let frame_duration = now.duration_since(last_frame_time);
-        last_frame_time = now;
-        let realtime_fps = 1.0 / frame_duration.as_secs_f32();
-        engine
-            .set_title(format!("3D Engine - {:.2?} FPS", realtime_fps))
-            .ok();
-
-        // Handle events
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => running = false, // Exit the loop on window close
-                Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => running = false, // Exit on Escape key
-                Event::KeyDown {
-                    keycode: Some(keycode),
-                    ..
-                } => {
-                    pressed_keys.insert(keycode);
-                }
-                Event::KeyUp {
-                    keycode: Some(keycode),
-                    ..
-                } => {
-                    pressed_keys.remove(&keycode);
-                }
-                Event::MouseMotion { xrel, yrel, .. } => {
-                    engine.rotate_camera(xrel as f32 * sensitivity, yrel as f32 * sensitivity);
-                }
-                Event::Window {
-                    win_event: WindowEvent::Resized(new_x, new_y),
-                    ..
-                } => engine.resize_window(new_x, new_y),
-                _ => {}
-            }
-        }
+    let meshes: Vec<Mesh> = matches
+        .get_many::<String>("object")
+        .unwrap_or_default()
+        .map(|object_to_load| load_mesh(object_to_load, &matches))
+        .collect::<Result<_, _>>()?;
 
-        if pressed_keys.contains(&Keycode::W) {
-            engine.move_camera(Keycode::W, last_frame_duration);
-        }
-        if pressed_keys.contains(&Keycode::S) {
-            engine.move_camera(Keycode::S, last_frame_duration);
-        }
-        if pressed_keys.contains(&Keycode::A) {
-            engine.move_camera(Keycode::A, last_frame_duration);
-        }
-        if pressed_keys.contains(&Keycode::D) {
-            engine.move_camera(Keycode::D, last_frame_duration);
-        }
-        if pressed_keys.contains(&Keycode::UP) {
-            engine.move_camera(Keycode::UP, last_frame_duration);
-        }
-        if pressed_keys.contains(&Keycode::DOWN) {
-            engine.move_camera(Keycode::DOWN, last_frame_duration);
-        }
-        if pressed_keys.contains(&Keycode::LEFT) {
-            engine.move_camera(Keycode::LEFT, last_frame_duration);
-        }
-        if pressed_keys.contains(&Keycode::RIGHT) {
-            engine.move_camera(Keycode::RIGHT, last_frame_duration);
+    if matches.get_flag("validate") {
+        for mesh in &meshes {
+            println!("{}", validate_mesh(mesh, DEFAULT_WELD_DISTANCE));
         }
+        return Ok(());
+    }
 
-        // Update the engine (call user-defined update logic)
-        if !engine.on_user_update() {
-            running = false;
-        }
+    let input = match matches.get_one::<String>("bindings") {
+        Some(filename) => InputMap::from_file(filename)?,
+        None => InputMap::new(),
+    };
 
-        // Frame delay to limit FPS
-        let frame_time = Instant::now().duration_since(last_frame_time);
-        if frame_time < target_frame_duration {
-            let sleep_duration = target_frame_duration - frame_time;
-            sleep(sleep_duration);
+    let lod_levels = matches.get_one::<usize>("lod-levels").cloned().unwrap_or(1);
+
+    let vsync = matches.get_flag("vsync");
+    let mut engine: Engine = Engine::new("3D Engine", 1280, 720, lod_levels, vsync);
+    if let Some(render_size) = matches.get_one::<(u32, u32)>("render-size") {
+        engine.set_render_size(Some(*render_size));
+    }
+
+    // The first object is placed in front of the camera, the others next to it
+    let mut position = Vector3D::from_coords(0.0, 0.0, 4.0);
+    let mut previous_radius: Option<f32> = None;
+    let mut bounding_spheres: Vec<(Vector3D, f32)> = vec![];
+    let mut centroid = Vector3D::new();
+    for mesh in meshes {
+        let (center, radius) = mesh.bounding_sphere();
+        if let Some(previous_radius) = previous_radius {
+            position.x += previous_radius + radius;
         }
-        last_frame_duration = duration_as_f32(frame_time);
+        previous_radius = Some(radius);
+        bounding_spheres.push((position + center, radius));
+        centroid += position + mesh.centroid();
+        engine.add_object(mesh, position);
     }
+    let (scene_center, scene_radius) = scene_bounds(&bounding_spheres);
 
-    Ok(())
+    // The orbit mode turns around the center of the models
+    if !bounding_spheres.is_empty() {
+        engine.camera_mut().target = centroid / bounding_spheres.len() as f32;
+    }
+    if matches.get_flag("orbit") {
+        // Far enough out to see the whole scene, wherever the models were placed
+        let camera = engine.camera_mut();
+        camera.set_orbit_target(camera.target, scene_radius * 2.5);
+    }
+    if let Some(render_mode) = matches.get_one::<RenderMode>("render-mode") {
+        engine.set_render_mode(*render_mode);
+    }
+    if let Some(cull_mode) = matches.get_one::<CullMode>("cull") {
+        engine.set_cull_mode(*cull_mode);
+    }
+
+    // A second camera looking down at the scene from the side, without perspective
+    let mut overview_camera = Camera::from_orthographic(scene_radius * 2.5);
+    overview_camera.position = scene_center + Vector3D::from_coords(-2.0, 2.0, -2.0) * scene_radius;
+    overview_camera.look_at(&scene_center);
+    engine.add_camera(overview_camera);
+
+    engine.set_input_map(input);
+    engine.hud_mut().visible = matches.get_flag("hud");
+    if let Some(filename) = matches.get_one::<String>("font") {
+        engine.hud_mut().font = Font::from_file(filename)?;
+    }
+
+    if let Some(frame_count) = matches.get_one::<usize>("benchmark") {
+        let mut event_pump = engine
+            .sdl_context
+            .event_pump()
+            .expect("Error creating event pump");
+        run_benchmark(
+            &mut engine,
+            &mut event_pump,
+            *frame_count,
+            &scene_center,
+            scene_radius,
+            matches.get_one::<String>("benchmark-csv"),
+        );
+        return Ok(());
+    }
+
+    // Movement is updated a fixed number of times per second, whatever the frame rate
+    engine.set_tick_rate(matches.get_one::<f32>("tick-rate").cloned().unwrap_or(60.0));
+    // 0 draws frames as fast as possible
+    let max_fps = matches.get_one::<f32>("max-fps").cloned().unwrap_or(60.0);
+    engine.set_max_fps((max_fps > 0.0).then_some(max_fps));
+
+    if let Some(filename) = matches.get_one::<String>("replay") {
+        engine.replay(filename)?;
+        engine.set_headless(matches.get_flag("headless"));
+    }
+    if let Some(filename) = matches.get_one::<String>("record") {
+        engine.record(filename);
+    }
+
+    let spin = matches.get_one::<f32>("spin").cloned().unwrap_or(0.0);
+    engine.run(&mut Viewer::new(spin.to_radians()))
+}
+
+fn load_mesh(object_to_load: &str, matches: &ArgMatches) -> Result<Mesh, String> {
+    let mut mesh = Mesh::from_file(object_to_load)?;
+
+    if matches.get_flag("repair") {
+        mesh.weld_vertices(DEFAULT_WELD_DISTANCE);
+        let removed = mesh.remove_degenerate_triangles();
+        let flipped = mesh.unify_orientation(DEFAULT_WELD_DISTANCE);
+        println!(
+            "Repaired mesh: removed {} degenerate triangles, flipped {} triangles",
+            removed, flipped
+        );
+    }
+
+    // Apply the requested normalisations, in a fixed order
+    if matches.get_flag("z-up") {
+        mesh.z_up_to_y_up();
+    }
+    if let Some(transform) = matches.get_one::<Matrix4X4>("transform") {
+        mesh.apply_transform(transform)
+            .map_err(|err| format!("{}: {}", object_to_load, err))?;
+    }
+    if matches.get_flag("recenter") {
+        mesh.recenter();
+    }
+    if matches.get_flag("unit-scale") {
+        mesh.scale_to_unit();
+    }
+    if matches.get_flag("flip-winding") {
+        mesh.flip_winding();
+    }
+
+    let target_triangles = matches.get_one::<usize>("simplify");
+    let max_error = matches.get_one::<f32>("simplify-error");
+    if target_triangles.is_some() || max_error.is_some() {
+        // Without a target, edges are collapsed until the error is reached
+        mesh = mesh.simplify(
+            target_triangles.copied().unwrap_or(0),
+            max_error.copied().unwrap_or(f32::INFINITY),
+        );
+        println!("Simplified mesh to {} triangles", mesh.triangles.len());
+    }
+
+    // Repairing can remove every triangle
+    if mesh.triangles.is_empty() {
+        return Err(format!("{}: no triangles left to draw", object_to_load));
+    }
+    Ok(mesh)
+}
+
+// A sphere around all the given spheres, not the smallest one but close enough
+fn scene_bounds(spheres: &[(Vector3D, f32)]) -> (Vector3D, f32) {
+    if spheres.is_empty() {
+        return (Vector3D::new(), 1.0);
+    }
+
+    let mut center = Vector3D::new();
+    for (sphere_center, _) in spheres {
+        center += sphere_center;
+    }
+    center /= spheres.len() as f32;
+
+    let radius = spheres
+        .iter()
+        .map(|(sphere_center, radius)| vector_len(&(sphere_center - center)) + radius)
+        .fold(0.0, f32::max);
+
+    (center, radius)
 }
 
 fn set_commands() -> ArgMatches {
@@ -128,20 +202,206 @@ fn set_commands() -> ArgMatches {
                 .long("max-fps")
                 .value_parser(clap::value_parser!(f32))
                 .default_value("60")
-                .help("Sets the maximum frames per second"),
+                .help("Sets the maximum frames per second, 0 for no limit"),
+        )
+        .arg(
+            Arg::new("tick-rate")
+                .long("tick-rate")
+                .value_parser(parse_tick_rate)
+                .default_value("60")
+                .help("Sets how many times per second movement is updated, independent of the frame rate"),
+        )
+        .arg(
+            Arg::new("vsync")
+                .long("vsync")
+                .action(ArgAction::SetTrue)
+                .help("Waits for the display when presenting a frame, instead of using --max-fps"),
+        )
+        .arg(
+            Arg::new("render-size")
+                .long("render-size")
+                .value_parser(parse_size)
+                .help("Renders at a fixed resolution like 320x180, scaled up to the window"),
+        )
+        .arg(
+            Arg::new("bindings")
+                .long("bindings")
+                .value_parser(clap::value_parser!(String))
+                .help("Loads key bindings and mouse settings from a file, see bindings.cfg"),
+        )
+        .arg(
+            Arg::new("record")
+                .long("record")
+                .value_parser(clap::value_parser!(String))
+                .conflicts_with("replay")
+                .help("Records all input and frame times to a file"),
+        )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_parser(clap::value_parser!(String))
+                .help("Replays a recording made with --record, saving marked frames as BMP files"),
+        )
+        .arg(
+            Arg::new("headless")
+                .long("headless")
+                .action(ArgAction::SetTrue)
+                .requires("replay")
+                .help("Replays as fast as possible without showing the window"),
+        )
+        .arg(
+            Arg::new("hud")
+                .long("hud")
+                .action(ArgAction::SetTrue)
+                .help("Starts with the HUD shown, it shows up in screenshots too"),
+        )
+        .arg(
+            Arg::new("font")
+                .long("font")
+                .value_parser(clap::value_parser!(String))
+                .help("Loads a PSF or BDF font for the HUD instead of the built-in one"),
         )
         .arg(
             Arg::new("object")
                 .long("object")
                 .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
                 .default_value("./teapot.obj")
-                .help("Load in a different obj file"),
+                .help("Load in a different obj file, can be given multiple times"),
+        )
+        .arg(
+            Arg::new("orbit")
+                .long("orbit")
+                .action(ArgAction::SetTrue)
+                .help("Starts with the camera orbiting around the models"),
+        )
+        .arg(
+            Arg::new("spin")
+                .long("spin")
+                .value_parser(clap::value_parser!(f32))
+                .allow_hyphen_values(true)
+                .help("Turns the models around their vertical axis, in degrees per second"),
+        )
+        .arg(
+            Arg::new("render-mode")
+                .long("render-mode")
+                .value_parser(parse_render_mode)
+                .help("Starts in a render mode: wireframe, solid, solid-wireframe, normals, depth or overdraw"),
+        )
+        .arg(
+            Arg::new("cull")
+                .long("cull")
+                .value_parser(parse_cull_mode)
+                .help("Which triangles are culled: off, back (the default) or front"),
+        )
+        .arg(
+            Arg::new("validate")
+                .long("validate")
+                .action(ArgAction::SetTrue)
+                .help("Prints a validation report of the object and exits"),
+        )
+        .arg(
+            Arg::new("repair")
+                .long("repair")
+                .action(ArgAction::SetTrue)
+                .help("Welds vertices, drops degenerate triangles and unifies winding on load"),
+        )
+        .arg(
+            Arg::new("recenter")
+                .long("recenter")
+                .action(ArgAction::SetTrue)
+                .help("Moves the center of the object to the origin"),
+        )
+        .arg(
+            Arg::new("unit-scale")
+                .long("unit-scale")
+                .action(ArgAction::SetTrue)
+                .help("Scales the object so its largest dimension is 1 unit"),
+        )
+        .arg(
+            Arg::new("z-up")
+                .long("z-up")
+                .action(ArgAction::SetTrue)
+                .help("Converts a Z-up object to Y-up"),
+        )
+        .arg(
+            Arg::new("flip-winding")
+                .long("flip-winding")
+                .action(ArgAction::SetTrue)
+                .help("Reverses the winding order of all triangles"),
+        )
+        .arg(
+            Arg::new("transform")
+                .long("transform")
+                .value_parser(|s: &str| s.parse::<Matrix4X4>())
+                .allow_hyphen_values(true)
+                .help("Bakes a 4x4 matrix (16 comma separated values, row-major) into the object"),
+        )
+        .arg(
+            Arg::new("simplify")
+                .long("simplify")
+                .value_parser(clap::value_parser!(usize))
+                .help("Decimates the object down to the given number of triangles"),
+        )
+        .arg(
+            Arg::new("simplify-error")
+                .long("simplify-error")
+                .value_parser(clap::value_parser!(f32))
+                .help("Decimates the object until every collapse would move the surface further than this distance, in model units"),
+        )
+        .arg(
+            Arg::new("lod-levels")
+                .long("lod-levels")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("1")
+                .help("Number of detail levels to generate, each with half the triangles"),
+        )
+        .arg(
+            Arg::new("benchmark")
+                .long("benchmark")
+                .value_parser(clap::value_parser!(usize))
+                .help("Renders the given number of frames along a fixed camera path, prints timings and exits"),
+        )
+        .arg(
+            Arg::new("benchmark-csv")
+                .long("benchmark-csv")
+                .value_parser(clap::value_parser!(String))
+                .requires("benchmark")
+                .help("Also writes the timings of every benchmark frame to a CSV file"),
         )
         .get_matches()
 }
 
-fn duration_as_f32(duration: Duration) -> f32 {
-    let seconds = duration.as_secs() as f32;
-    let nanos = duration.subsec_nanos() as f32;
-    seconds + (nanos / 1_000_000_000.0)
+// Parses a size like "320x180"
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let (size_x, size_y) = s
+        .split_once('x')
+        .ok_or_else(|| format!("Expected a size like 320x180, got {}", s))?;
+    let parse = |value: &str| match value.trim().parse::<u32>() {
+        Ok(value) if value > 0 => Ok(value),
+        _ => Err(format!("Invalid size: {}", s)),
+    };
+
+    Ok((parse(size_x)?, parse(size_y)?))
+}
+
+fn parse_tick_rate(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(tick_rate) if tick_rate > 0.0 => Ok(tick_rate),
+        _ => Err(format!(
+            "Expected a positive number of updates per second, got {}",
+            s
+        )),
+    }
+}
+
+fn parse_render_mode(s: &str) -> Result<RenderMode, String> {
+    RenderMode::from_name(s).ok_or_else(|| {
+        let names: Vec<&str> = RenderMode::ALL.iter().map(|mode| mode.name()).collect();
+        format!("Expected one of {}, got {}", names.join(", "), s)
+    })
+}
+
+fn parse_cull_mode(s: &str) -> Result<CullMode, String> {
+    CullMode::from_name(s).ok_or_else(|| format!("Expected off, back or front, got {}", s))
 }
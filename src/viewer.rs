@@ -0,0 +1,149 @@
+use driedee_rs::core::{
+    application::Application, engine::Engine, input::Action, quaternion::Quaternion,
+    vector_3d::Vector3D,
+};
+use sdl2::{event::Event, pixels::Color};
+
+// Actions that are applied every update step while their keys are held down
+const MOVEMENT_ACTIONS: [Action; 10] = [
+    Action::MoveForward,
+    Action::MoveBackward,
+    Action::StrafeLeft,
+    Action::StrafeRight,
+    Action::Ascend,
+    Action::Descend,
+    Action::TurnLeft,
+    Action::TurnRight,
+    Action::RollLeft,
+    Action::RollRight,
+];
+
+// The model viewer: fly or orbit around the loaded models, pick triangles and
+// switch between cameras
+pub struct Viewer {
+    // Radians per second the models turn around their vertical axis
+    spin: f32,
+}
+
+impl Viewer {
+    pub fn new(spin: f32) -> Viewer {
+        Viewer { spin }
+    }
+}
+
+impl Application for Viewer {
+    fn on_user_create(&mut self, engine: &mut Engine) -> bool {
+        let mouse_util = engine.sdl_context.mouse();
+        mouse_util.set_relative_mouse_mode(true);
+        mouse_util.show_cursor(false);
+        true
+    }
+
+    fn on_user_input(&mut self, engine: &mut Engine, event: &Event, actions: &[Action]) {
+        for action in actions {
+            match action {
+                Action::SubdivideMore | Action::SubdivideLess => {
+                    let delta = if *action == Action::SubdivideMore {
+                        1
+                    } else {
+                        -1
+                    };
+                    let level = engine.change_subdivision_level(delta);
+                    println!(
+                        "Subdivision level {}: {} triangles",
+                        level,
+                        engine.triangle_count()
+                    );
+                }
+                Action::NextCamera => {
+                    let camera = engine.next_camera();
+                    println!("Switched to camera {}", camera);
+                }
+                Action::ToggleProjection => engine.camera_mut().toggle_projection(),
+                Action::ToggleOrbit => engine.camera_mut().toggle_mode(),
+                Action::NextRenderMode => {
+                    let render_mode = engine.next_render_mode();
+                    println!("Render mode: {}", render_mode.name());
+                }
+                Action::NextCullMode => {
+                    let cull_mode = engine.next_cull_mode();
+                    println!("Culling: {}", cull_mode.name());
+                }
+                Action::Select => {
+                    // The cursor is hidden in relative mouse mode, so pick at the center
+                    let (size_x, size_y) = engine.size();
+                    match engine.select(size_x as i32 / 2, size_y as i32 / 2) {
+                        Some(pick) => println!(
+                            "Picked object {}, triangle {} at ({:.3}, {:.3}, {:.3})",
+                            pick.object, pick.triangle, pick.point.x, pick.point.y, pick.point.z
+                        ),
+                        None => println!("Nothing picked"),
+                    }
+                }
+                // Movement and panning last as long as the keys are held
+                _ => {}
+            }
+        }
+
+        match *event {
+            Event::MouseWheel { y, .. } => engine.camera_mut().zoom(y as f32),
+            Event::MouseMotion { xrel, yrel, .. } if engine.input().is_held(Action::Pan) => {
+                engine.pan_camera(xrel as f32, yrel as f32);
+            }
+            Event::MouseMotion { xrel, yrel, .. } => {
+                let (rel_x, rel_y) = engine.input().mouse_motion(xrel, yrel);
+                engine.rotate_camera(rel_x, rel_y);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_fixed_update(&mut self, engine: &mut Engine, step: f32) {
+        for action in MOVEMENT_ACTIONS {
+            if engine.input().is_held(action) {
+                engine.move_camera(action, step);
+            }
+        }
+
+        if self.spin != 0.0 {
+            let up = Vector3D::from_coords(0.0, 1.0, 0.0);
+            engine.rotate_objects(&Quaternion::from_axis_angle(&up, self.spin * step));
+        }
+    }
+
+    fn on_user_update(&mut self, engine: &mut Engine, elapsed_time: f32) -> bool {
+        let realtime_fps = 1.0 / elapsed_time;
+        engine
+            .set_title(format!(
+                "3D Engine - {:.2?} FPS - {}/{} objects culled",
+                realtime_fps,
+                engine.stats().objects_culled,
+                engine.object_count()
+            ))
+            .ok();
+
+        engine.render_scene();
+
+        // Selecting picks what is under the crosshair
+        let (size_x, size_y) = engine.size();
+        let (center_x, center_y) = (size_x as i32 / 2, size_y as i32 / 2);
+        let crosshair_color = Color::RGBA(255, 255, 255, 160);
+        let frame_buffer = engine.frame_buffer_mut();
+        frame_buffer.draw_line(
+            center_x - 6,
+            center_y,
+            center_x + 6,
+            center_y,
+            crosshair_color,
+        );
+        frame_buffer.draw_line(
+            center_x,
+            center_y - 6,
+            center_x,
+            center_y + 6,
+            crosshair_color,
+        );
+
+        true
+    }
+}